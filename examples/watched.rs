@@ -8,7 +8,7 @@ use ipc_watcher::{shared_memory_create, Watched};
 struct State([u8; 8]);
 
 fn main() {
-    let mut mem = shared_memory_create("./example_watched", 36).unwrap();
+    let mut mem = shared_memory_create("./example_watched", 1024).unwrap();
 
     let watched = Watched::<State>::new_from_mem(&mut mem);
 
@@ -6,7 +6,7 @@ use ipc_watcher::{shared_memory_open, Watcher};
 struct State([u8; 8]);
 
 fn main() {
-    let mut mem = shared_memory_open("./example_watched", 36).unwrap();
+    let mut mem = shared_memory_open("./example_watched", 1024).unwrap();
 
     let watched = Watcher::<State>::new_from_mem(&mut mem);
 
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use crate::{Error, PollResult, RwLockSync, SyncStrategy, Watcher};
+
+/// Running statistics of values observed by [AccumulatingWatcher].
+/// All fields are zero before any value is observed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub count: u64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+impl Stats {
+    fn push(&mut self, val: f64) {
+        if self.count == 0 {
+            self.min = val;
+            self.max = val;
+        } else {
+            self.min = self.min.min(val);
+            self.max = self.max.max(val);
+        }
+        self.count += 1;
+        self.mean += (val - self.mean) / self.count as f64;
+    }
+}
+
+/// A [Watcher] maintaining [Stats] of a numeric projection of observed values.
+/// Constructed by [Watcher::accumulate].
+///
+/// Only changes observed by this watcher are accumulated. Writes overwritten before being
+/// observed are not counted. See [Watcher::overruns].
+pub struct AccumulatingWatcher<'a, T, F, S = RwLockSync>
+where
+    T: Copy + Send + Sync,
+    S: SyncStrategy,
+{
+    watcher: Watcher<'a, T, S>,
+    project: F,
+    stats: Stats,
+}
+
+impl<'a, T, F, S> AccumulatingWatcher<'a, T, F, S>
+where
+    T: Copy + Send + Sync,
+    F: Fn(&T) -> f64,
+    S: SyncStrategy,
+{
+    /// Observe the value change of T and accumulate the new value. See [Watcher::has_changed].
+    pub fn has_changed(&mut self) -> bool {
+        let changed = self.watcher.has_changed();
+        if changed {
+            self.accumulate();
+        }
+        changed
+    }
+
+    /// Block current thread until value of T changed and accumulate the new value.
+    /// See [Watcher::wait_for_change].
+    pub fn wait_for_change(&mut self) -> Result<(), Error> {
+        self.watcher.wait_for_change()?;
+        self.accumulate();
+        Ok(())
+    }
+
+    /// Same as [Watcher::poll_timeout] and accumulate the changed value.
+    pub fn poll_timeout(&mut self, timeout: Duration) -> PollResult<T> {
+        let res = self.watcher.poll_timeout(timeout);
+        if let PollResult::Changed(ref val) = res {
+            self.stats.push((self.project)(val));
+        }
+        res
+    }
+
+    /// Statistics of all observed values.
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Access the inner [Watcher].
+    pub fn watcher(&self) -> &Watcher<'a, T, S> {
+        &self.watcher
+    }
+
+    /// Return the inner [Watcher] and drop statistics.
+    pub fn into_inner(self) -> Watcher<'a, T, S> {
+        self.watcher
+    }
+
+    fn accumulate(&mut self) {
+        let val = self.watcher.read(&self.project);
+        self.stats.push(val);
+    }
+}
+
+impl<'a, T: Copy + Send + Sync, S: SyncStrategy> Watcher<'a, T, S> {
+    /// Convert to an [AccumulatingWatcher] maintaining [Stats] of `project` over the
+    /// observed values.
+    pub fn accumulate<F>(self, project: F) -> AccumulatingWatcher<'a, T, F, S>
+    where
+        F: Fn(&T) -> f64,
+    {
+        AccumulatingWatcher {
+            watcher: self,
+            project,
+            stats: Stats::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{shared_memory_create, shared_memory_open, Watched};
+
+    #[test]
+    fn accumulate() {
+        let path = "./test_accumulate";
+
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = Watched::<(u8, u32)>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open(path, 1024).unwrap();
+        let mut watcher = Watcher::<(u8, u32)>::new_from_mem(&mut mem2).accumulate(|v| v.1 as f64);
+        assert_eq!(watcher.stats(), Stats::default());
+
+        for val in [4, 2, 9] {
+            watched.write((0, val));
+            assert!(watcher.has_changed());
+        }
+        watched.write((0, 1));
+        assert_eq!(
+            watcher.poll_timeout(Duration::ZERO),
+            PollResult::Changed((0, 1))
+        );
+
+        // overwritten value is not accumulated.
+        watched.write((0, 100));
+        watched.write((0, 4));
+        watcher.wait_for_change().unwrap();
+
+        assert_eq!(
+            watcher.stats(),
+            Stats {
+                count: 5,
+                min: 1.0,
+                max: 9.0,
+                mean: 4.0,
+            }
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use std::{io, ptr};
+
+use crate::{error::Error, shared::Memory};
+
+/// Anonymous shared memory without any file or name.
+///
+/// The memory can only be shared with child processes by `fork`. It must be created before
+/// `fork` so parent and child inherit the same mapping. A mapping created after `fork` is
+/// private to the process.
+///
+/// A child process should construct it's [Watcher](crate::Watcher) or
+/// [Watched](crate::Watched) after `fork` and avoid touching the ones inherited from parent.
+pub struct AnonMem {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl AnonMem {
+    /// Map anonymous shared memory with given size.
+    pub fn new(size: usize) -> Result<Self, Error> {
+        // SAFETY:
+        // mmap with null address does not touch existing mappings.
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+
+        Ok(Self {
+            ptr: ptr as *mut u8,
+            len: size,
+        })
+    }
+
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+}
+
+impl Memory for AnonMem {
+    fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl Drop for AnonMem {
+    fn drop(&mut self) {
+        // SAFETY:
+        // ptr and len are from the mmap call in AnonMem::new.
+        unsafe {
+            libc::munmap(self.ptr as *mut _, self.len);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::{marker::PhantomData, sync::atomic::Ordering};
+
+use shared_memory::Shmem;
+
+#[cfg(unix)]
+use crate::AnonMem;
+use crate::{
+    error::Error,
+    shared::{Memory, Shared},
+    sync::{RwLockSync, SyncStrategy},
+    Watched,
+};
+
+/// Builder of [Watched] value with extra configuration.
+pub struct WatchedBuilder<T, S = RwLockSync> {
+    data_align: usize,
+    prefault: bool,
+    data: PhantomData<(T, S)>,
+}
+
+impl<T: Copy + Send + Sync, S: SyncStrategy> Default for WatchedBuilder<T, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + Send + Sync, S: SyncStrategy> WatchedBuilder<T, S> {
+    pub fn new() -> Self {
+        Self {
+            data_align: 1,
+            prefault: false,
+            data: PhantomData,
+        }
+    }
+
+    /// Align the start of data region to `align` bytes. Useful for keeping data on it's own
+    /// cache line or page. The padding is counted in the required size of shared memory.
+    /// The data region is always aligned to `T` and alignment up to page size is supported.
+    ///
+    /// Alignment is recorded in header of shared memory so watchers always agree with it.
+    /// When the shared memory is already reserved by [Watcher](crate::Watcher) with a
+    /// different alignment [WatchedBuilder::build] returns [Error::Misaligned].
+    ///
+    /// # panics:
+    ///
+    /// When `align` is not power of two.
+    pub fn data_align(mut self, align: usize) -> Self {
+        assert!(align.is_power_of_two(), "data_align must be power of two");
+        self.data_align = align;
+        self
+    }
+
+    /// Touch every page of shared memory on construction. See [Watched::prefault].
+    pub fn prefault(mut self, prefault: bool) -> Self {
+        self.prefault = prefault;
+        self
+    }
+
+    /// Construct a new watched value in given [Shmem]. See [Watched::new_from_mem].
+    ///
+    /// Shared memory is validated the same as [Watched::try_from]. Data offset is checked to
+    /// be aligned to both `T` and [WatchedBuilder::data_align].
+    pub fn build(self, mem: &mut Shmem) -> Result<Watched<'_, T, S>, Error> {
+        self.build_in(mem)
+    }
+
+    /// Construct a new watched value in given [AnonMem]. See [Watched::new_from_anon].
+    #[cfg(unix)]
+    pub fn build_anon(self, mem: &mut AnonMem) -> Result<Watched<'_, T, S>, Error> {
+        self.build_in(mem)
+    }
+
+    fn build_in<M: Memory>(self, mem: &M) -> Result<Watched<'_, T, S>, Error> {
+        let shared = Shared::try_from_mem_aligned(mem, self.data_align)?;
+        if self.prefault {
+            shared.prefault();
+        }
+        {
+            // tick and generation are changed together with write lock held so readers
+            // always see them consistent with the value.
+            let _guard = shared.lock.lock().unwrap();
+            shared.header.written.store(0, Ordering::SeqCst);
+            shared.header.reset_meta();
+            // tick is reset along with meta fields so watchers see it with the new generation.
+            shared.header.write_meta(|header| {
+                shared.tick.store(0);
+                header.sequence.store(0, Ordering::SeqCst);
+                header.generation.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        Ok(Watched(shared, Default::default(), None))
+    }
+}
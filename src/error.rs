@@ -1,5 +1,134 @@
-//! Placeholder error type.
+//! Error type of this crate.
 
-use std::error;
+use std::{error, fmt, io};
 
-pub type Error = Box<dyn error::Error + Send + Sync + 'static>;
+use shared_memory::ShmemError;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to create or open shared memory.
+    Shmem(ShmemError),
+    /// Failed to map anonymous shared memory.
+    Io(io::Error),
+    /// Shared memory is not large enough for the shared value.
+    MemoryTooSmall { required: usize, actual: usize },
+    /// Layout of shared memory is not internally consistent.
+    InvalidLayout { reason: &'static str },
+    /// Data of shared memory initialized by others is not aligned to required bytes.
+    Misaligned { align: usize },
+    /// Shared memory is initialized for a type with different size or alignment. Writer is the
+    /// one initialized the shared memory and reader is the one attaching to it.
+    LayoutMismatch {
+        writer_size: usize,
+        reader_size: usize,
+        writer_align: usize,
+        reader_align: usize,
+    },
+    /// Shared memory is initialized with a tick counter of different width in bytes.
+    TickWidthMismatch { expected: u8, found: u8 },
+    /// Shared memory is initialized with a different [SyncStrategy](crate::SyncStrategy).
+    SyncMismatch { expected: u8, found: u8 },
+    /// Name of value in [Registry](crate::Registry) is empty, `.`, `..` or contains a path
+    /// separator.
+    InvalidName { name: String },
+    /// String is longer than capacity of [WatchedString](crate::WatchedString).
+    TooLong { len: usize, cap: usize },
+    /// Tick observed by [Watcher](crate::Watcher) went backward. See
+    /// [Watcher::observed_out_of_order](crate::Watcher::observed_out_of_order).
+    OutOfOrder { last: u8, found: u8 },
+    /// Snapshot is not the size of shared value.
+    SnapshotSize { expected: usize, found: usize },
+    /// Value given to [Watched::write_guarded](crate::Watched::write_guarded) does not hold
+    /// the invariant.
+    InvariantViolated,
+    /// Header of shared memory is not initialized yet.
+    Uninitialized,
+    /// Header of shared memory is being initialized by another one not finishing in time.
+    /// Happens when it's process died in the middle of initialization.
+    InitStalled,
+    /// Lock is held by writer after retry budget is exhausted.
+    TooContended,
+    /// [Watched](crate::Watched) value is gone.
+    Closed,
+    /// Waiting is timed out.
+    TimedOut,
+    /// Waiting is cancelled by [CancelToken](crate::CancelToken).
+    Cancelled,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Shmem(ref e) => write!(f, "Shared memory error: {}", e),
+            Self::Io(ref e) => write!(f, "Io error: {}", e),
+            Self::MemoryTooSmall { required, actual } => write!(
+                f,
+                "Shared memory not enough, {} extra bytes needed",
+                required - actual
+            ),
+            Self::InvalidLayout { reason } => write!(f, "Invalid shared memory layout: {}", reason),
+            Self::Misaligned { align } => {
+                write!(f, "Shared data is not aligned to {} bytes", align)
+            }
+            Self::LayoutMismatch {
+                writer_size,
+                reader_size,
+                writer_align,
+                reader_align,
+            } => write!(
+                f,
+                "Shared type layout mismatch, size {} and align {} expected, found size {} and align {}",
+                writer_size, writer_align, reader_size, reader_align
+            ),
+            Self::TickWidthMismatch { expected, found } => write!(
+                f,
+                "Shared memory use tick of {} bytes, expected {}",
+                found, expected
+            ),
+            Self::SyncMismatch { expected, found } => write!(
+                f,
+                "Shared memory use sync strategy {}, expected {}",
+                found, expected
+            ),
+            Self::InvalidName { ref name } => write!(f, "Invalid value name: {:?}", name),
+            Self::TooLong { len, cap } => {
+                write!(
+                    f,
+                    "String of {} bytes exceeds capacity of {} bytes",
+                    len, cap
+                )
+            }
+            Self::OutOfOrder { last, found } => {
+                write!(f, "Tick went backward from {} to {}", last, found)
+            }
+            Self::SnapshotSize { expected, found } => write!(
+                f,
+                "Snapshot of {} bytes does not match value of {} bytes",
+                found, expected
+            ),
+            Self::InvariantViolated => f.write_str("Value violates invariant"),
+            Self::Uninitialized => f.write_str("Shared memory is not initialized"),
+            Self::InitStalled => f.write_str("Shared memory initialization is stalled"),
+            Self::TooContended => f.write_str("Shared value is too contended"),
+            Self::Closed => f.write_str("Watched value is gone"),
+            Self::TimedOut => f.write_str("Waiting is timed out"),
+            Self::Cancelled => f.write_str("Waiting is cancelled"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Self::Shmem(ref e) => Some(e),
+            Self::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ShmemError> for Error {
+    fn from(e: ShmemError) -> Self {
+        Self::Shmem(e)
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::{CancelToken, OwnedWatcher, SyncStrategy};
+
+// OwnedWatcher moved to bridge thread.
+struct SendWatcher<T: Copy + Send + Sync, S: SyncStrategy>(OwnedWatcher<T, S>);
+
+// SAFETY:
+// OwnedWatcher owns it's shared memory and all state. Lock in shared memory is process shared
+// and never held across threads. It's only used by the bridge thread after moved.
+unsafe impl<T: Copy + Send + Sync, S: SyncStrategy> Send for SendWatcher<T, S> {}
+
+// None after bridge stopped.
+type Subscribers<T> = Arc<Mutex<Option<Vec<SyncSender<T>>>>>;
+
+/// A bridge forwarding every change observed by one [OwnedWatcher] to many in process
+/// subscribers. Constructed by [Watcher::fan_out](crate::Watcher::fan_out).
+///
+/// The watcher is moved to a bridge thread waiting for change and reading the new value once
+/// for all subscribers. Each subscriber has it's own queue of given capacity. When a queue is
+/// full the new value is dropped for that subscriber and counted by [FanOut::dropped] so a
+/// slow subscriber never block others. Values overwritten in shared memory before the bridge
+/// observes them are never forwarded.
+///
+/// The bridge stops when [Watched](crate::Watched) value is gone or this value is dropped.
+/// Receivers of subscribers are disconnected after.
+pub struct FanOut<T> {
+    subscribers: Subscribers<T>,
+    capacity: usize,
+    dropped: Arc<AtomicUsize>,
+    token: CancelToken,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl<T: Copy + Send + Sync + 'static> FanOut<T> {
+    pub(crate) fn spawn<S: SyncStrategy + 'static>(
+        watcher: OwnedWatcher<T, S>,
+        capacity: usize,
+    ) -> Self {
+        let subscribers: Subscribers<T> = Arc::new(Mutex::new(Some(Vec::new())));
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let token = CancelToken::new();
+
+        let watcher = SendWatcher(watcher);
+        let handle = {
+            let subscribers = subscribers.clone();
+            let dropped = dropped.clone();
+            let token = token.clone();
+            thread::spawn(move || {
+                let mut watcher = watcher;
+                while watcher.0.wait_for_change_cancellable(&token).is_ok() {
+                    let val = watcher.0.read(|val| *val);
+                    let mut subscribers = subscribers.lock().unwrap();
+                    subscribers
+                        .as_mut()
+                        .unwrap()
+                        .retain(|tx| match tx.try_send(val) {
+                            Ok(_) => true,
+                            Err(TrySendError::Full(_)) => {
+                                dropped.fetch_add(1, Ordering::Relaxed);
+                                true
+                            }
+                            Err(TrySendError::Disconnected(_)) => false,
+                        });
+                }
+                subscribers.lock().unwrap().take();
+            })
+        };
+
+        Self {
+            subscribers,
+            capacity,
+            dropped,
+            token,
+            handle: Some(handle),
+        }
+    }
+
+    /// Subscribe to values observed from now on.
+    /// Receiver is disconnected when the bridge stops.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let (tx, rx) = mpsc::sync_channel(self.capacity);
+        // sender is dropped when bridge already stopped so receiver is disconnected.
+        if let Some(subscribers) = self.subscribers.lock().unwrap().as_mut() {
+            subscribers.push(tx);
+        }
+        rx
+    }
+
+    /// Total count of values dropped for subscribers with full queue.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> Drop for FanOut<T> {
+    fn drop(&mut self) {
+        self.token.cancel();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    use crate::{shared_memory_create, shared_memory_open, Watched, Watcher};
+
+    #[test]
+    fn fan_out() {
+        let path = "./test_fan_out";
+
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = Watched::<u32>::new_from_mem(&mut mem);
+        let watcher = Watcher::<u32>::from_owned(shared_memory_open(path, 1024).unwrap()).unwrap();
+
+        let fan_out = watcher.fan_out(1);
+        let rx1 = fan_out.subscribe();
+        let rx2 = fan_out.subscribe();
+        let timeout = Duration::from_secs(1);
+
+        watched.write(1);
+        assert_eq!(rx1.recv_timeout(timeout), Ok(1));
+        assert_eq!(rx2.recv_timeout(timeout), Ok(1));
+
+        // rx2 is not received and it's queue is full.
+        watched.write(2);
+        assert_eq!(rx1.recv_timeout(timeout), Ok(2));
+        watched.write(3);
+        assert_eq!(rx1.recv_timeout(timeout), Ok(3));
+        // wait for bridge to finish forwarding 3 by taking the subscriber lock.
+        drop(fan_out.subscribe());
+        assert_eq!(fan_out.dropped(), 1);
+        assert_eq!(rx2.recv_timeout(timeout), Ok(2));
+
+        // dropped subscriber is removed.
+        drop(rx1);
+        watched.write(4);
+        assert_eq!(rx2.recv_timeout(timeout), Ok(4));
+
+        drop(watched);
+        assert_eq!(
+            rx2.recv_timeout(timeout),
+            Err(mpsc::RecvTimeoutError::Disconnected)
+        );
+        assert!(fan_out.subscribe().recv().is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use shared_memory::Shmem;
+
+use crate::{Error, RwLockSync, SyncStrategy, Watched, Watcher};
+
+/// `N` slots of `T` sharing one lock and one tick.
+///
+/// Slots are written together by [WatchedGroup::write_all] and observed by [WatcherGroup]
+/// with the same `T` and `N`. It's for logically coupled values that should always be
+/// observed together.
+///
+/// Observation is all or nothing. A write of any slot is one change of the group and
+/// watchers always see either all slots before a write or all slots after it.
+pub struct WatchedGroup<'a, T, const N: usize, S = RwLockSync>(Watched<'a, [T; N], S>)
+where
+    T: Copy + Send + Sync,
+    S: SyncStrategy;
+
+impl<'a, T, const N: usize, S> WatchedGroup<'a, T, N, S>
+where
+    T: Copy + Send + Sync,
+    S: SyncStrategy,
+{
+    /// Construct a new group of watched values in given [Shmem]. See [Watched::new_from_mem].
+    ///
+    /// # panics:
+    ///
+    /// When [Shmem] is not valid for `N` slots of `T`.
+    pub fn new_from_mem(mem: &'a mut Shmem) -> Self {
+        Self::try_from(mem).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Obtain a write lock and modify all slots in place through a closure.
+    /// Watchers are notified once after the closure returns.
+    pub fn write_all<F>(&self, func: F)
+    where
+        F: FnOnce(&mut [T; N]),
+    {
+        self.0.modify(|_, slots| func(slots));
+    }
+
+    /// Obtain a write lock and write a new `T` to the slot at `idx`.
+    ///
+    /// # panics:
+    ///
+    /// When `idx` is out of range of `N`.
+    pub fn write_slot(&self, idx: usize, value: T) {
+        self.write_all(|slots| slots[idx] = value);
+    }
+}
+
+/// Return [Error::MemoryTooSmall] when [Shmem] is not large enough for `N` slots of `T`.
+impl<'a, T, const N: usize, S> TryFrom<&'a mut Shmem> for WatchedGroup<'a, T, N, S>
+where
+    T: Copy + Send + Sync,
+    S: SyncStrategy,
+{
+    type Error = Error;
+
+    fn try_from(mem: &'a mut Shmem) -> Result<Self, Self::Error> {
+        Watched::try_from(mem).map(Self)
+    }
+}
+
+/// Reader of a [WatchedGroup] with the same `T` and `N`.
+pub struct WatcherGroup<'a, T, const N: usize, S = RwLockSync>(Watcher<'a, [T; N], S>)
+where
+    T: Copy + Send + Sync,
+    S: SyncStrategy;
+
+impl<'a, T, const N: usize, S> WatcherGroup<'a, T, N, S>
+where
+    T: Copy + Send + Sync,
+    S: SyncStrategy,
+{
+    /// Construct a new observer of group from given [Shmem]. See [Watcher::new_from_mem].
+    ///
+    /// # panics:
+    ///
+    /// When [Shmem] is not valid for `N` slots of `T`.
+    pub fn new_from_mem(mem: &'a mut Shmem) -> Self {
+        Self::try_from(mem).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Observe the change of any slot. See [Watcher::has_changed].
+    pub fn has_changed(&mut self) -> bool {
+        self.0.has_changed()
+    }
+
+    /// Block current thread until any slot changed. See [Watcher::wait_for_change].
+    pub fn wait_for_change(&mut self) -> Result<(), Error> {
+        self.0.wait_for_change()
+    }
+
+    /// Obtain a read lock and access all slots through a closure.
+    pub fn read_all<F, O>(&self, func: F) -> O
+    where
+        F: FnOnce(&[T; N]) -> O,
+    {
+        self.0.read(func)
+    }
+}
+
+/// Return [Error::MemoryTooSmall] when [Shmem] is not large enough for `N` slots of `T`.
+impl<'a, T, const N: usize, S> TryFrom<&'a mut Shmem> for WatcherGroup<'a, T, N, S>
+where
+    T: Copy + Send + Sync,
+    S: SyncStrategy,
+{
+    type Error = Error;
+
+    fn try_from(mem: &'a mut Shmem) -> Result<Self, Self::Error> {
+        Watcher::try_from(mem).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{shared_memory_create, shared_memory_open};
+
+    #[test]
+    fn write_all() {
+        let path = "./test_group";
+
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = WatchedGroup::<u32, 3>::new_from_mem(&mut mem);
+
+        let handle = std::thread::spawn(move || {
+            let mut mem = shared_memory_open(path, 1024).unwrap();
+            let mut watcher = WatcherGroup::<u32, 3>::new_from_mem(&mut mem);
+            while watcher.wait_for_change().is_ok() {
+                let done = watcher.read_all(|slots| {
+                    assert!(slots.iter().all(|s| *s == slots[0]));
+                    slots[0] == 1000
+                });
+                if done {
+                    break;
+                }
+            }
+        });
+
+        for i in 1..=1000 {
+            watched.write_all(|slots| slots.iter_mut().for_each(|s| *s = i));
+        }
+        handle.join().unwrap();
+
+        let mut mem = shared_memory_open(path, 1024).unwrap();
+        let mut watcher = WatcherGroup::<u32, 3>::new_from_mem(&mut mem);
+        watched.write_slot(1, 7);
+        assert!(watcher.has_changed());
+        watcher.read_all(|slots| assert_eq!(slots, &[1000, 7, 1000]));
+    }
+}
@@ -0,0 +1,288 @@
+use std::{
+    hint, mem,
+    sync::atomic::{AtomicU32, AtomicU64, AtomicU8, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::error::Error;
+
+// States of Header::init.
+const UNINIT: u8 = 0;
+const BUSY: u8 = 1;
+const READY: u8 = 2;
+
+// Max time of waiting for header initialized by others. Initialization only writes a few
+// fields so only an initializer died in the middle of it can take longer.
+const INIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Count of ack cursor slots. Watchers attached after all slots are taken have no cursor.
+pub(crate) const ACK_SLOTS: usize = 32;
+
+// Flag of ack slot taken by a watcher. Lower bits are the acked count of published writes.
+const ACK_TAKEN: u64 = 1 << 63;
+
+// Max retries of reading meta fields while they are being written. Only a writer died in
+// the middle of writing can keep the seqlock odd so the last read is returned after.
+const META_RETRY: usize = 1 << 16;
+
+// Header placed at the start of shared memory. Followed by rwlock and data.
+#[repr(C)]
+pub(crate) struct Header {
+    // a newly created shared memory is zeroed and start with UNINIT state.
+    init: AtomicU8,
+    pub(crate) tick: AtomicU8,
+    // set by the first write of current Watched.
+    pub(crate) written: AtomicU8,
+    // width of tick counter in bytes. decided by the initializer.
+    pub(crate) tick_width: AtomicU8,
+    // id of SyncStrategy. decided by the initializer.
+    pub(crate) sync: AtomicU8,
+    // offset of data from the start of shared memory. decided by the initializer.
+    pub(crate) data_off: AtomicU32,
+    // size and alignment of T. decided by the initializer.
+    pub(crate) value_size: AtomicU32,
+    pub(crate) value_align: AtomicU32,
+    // increased every time a new Watched take over the shared memory.
+    pub(crate) generation: AtomicU32,
+    // generation of the Watched that wrote the value. 0 before any write. changed with write
+    // lock held.
+    pub(crate) value_generation: AtomicU32,
+    // increased by Watched::force_resync.
+    pub(crate) resync: AtomicU32,
+    // application controlled sequence. not related to tick.
+    pub(crate) sequence: AtomicU64,
+    // count of attached Watcher.
+    pub(crate) watchers: AtomicU32,
+    // count of published writes since shared memory is created. increased with write lock
+    // held and never wraps.
+    pub(crate) published: AtomicU64,
+    // ack cursors of registered watchers.
+    acks: [AtomicU64; ACK_SLOTS],
+    // seqlock of meta fields (generation and sequence). odd while they are being written.
+    meta_seq: AtomicU32,
+    // count of writes since shared memory is created. only increased with metrics feature but
+    // always reserved so builds with and without it agree on layout.
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    pub(crate) total_writes: AtomicU64,
+}
+
+impl Header {
+    // SAFETY:
+    // Caller must make sure given pointer is valid and aligned for the lifetime of Header.
+    pub(crate) unsafe fn from_ptr<'a>(ptr: *mut u8) -> (&'a Self, usize) {
+        let header = &*(ptr as *const Self);
+        (header, mem::size_of::<Self>())
+    }
+
+    // write meta fields in closure. readers with Header::read_meta never observe a partial
+    // write of them.
+    pub(crate) fn write_meta<F>(&self, func: F)
+    where
+        F: FnOnce(&Self),
+    {
+        let mut seq = self.meta_seq.load(Ordering::SeqCst);
+        loop {
+            if seq & 1 == 1 {
+                hint::spin_loop();
+                seq = self.meta_seq.load(Ordering::SeqCst);
+                continue;
+            }
+            match self.meta_seq.compare_exchange_weak(
+                seq,
+                seq.wrapping_add(1),
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => break,
+                Err(s) => seq = s,
+            }
+        }
+
+        func(self);
+
+        self.meta_seq.store(seq.wrapping_add(2), Ordering::SeqCst);
+    }
+
+    // read meta fields in closure and retry when they are written concurrently.
+    pub(crate) fn read_meta<F, O>(&self, func: F) -> O
+    where
+        F: Fn(&Self) -> O,
+    {
+        let mut retry = 0;
+        loop {
+            let seq = self.meta_seq.load(Ordering::SeqCst);
+            let out = func(self);
+            if (seq & 1 == 0 && self.meta_seq.load(Ordering::SeqCst) == seq) || retry == META_RETRY
+            {
+                return out;
+            }
+            retry += 1;
+            if retry < 64 {
+                hint::spin_loop();
+            } else {
+                thread::yield_now();
+            }
+        }
+    }
+
+    // clear seqlock left odd by a previous writer died in the middle of writing.
+    // only called by a new Watched taking over the shared memory.
+    pub(crate) fn reset_meta(&self) {
+        self.meta_seq.fetch_and(!1, Ordering::SeqCst);
+    }
+
+    // take a free ack slot with given initial count of published writes. None when all slots
+    // are taken.
+    pub(crate) fn register_ack(&self, published: u64) -> Option<usize> {
+        self.acks.iter().position(|slot| {
+            slot.compare_exchange(0, ACK_TAKEN | published, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+        })
+    }
+
+    pub(crate) fn ack(&self, slot: usize, published: u64) {
+        self.acks[slot].store(ACK_TAKEN | published, Ordering::SeqCst);
+    }
+
+    pub(crate) fn release_ack(&self, slot: usize) {
+        self.acks[slot].store(0, Ordering::SeqCst);
+    }
+
+    // acked counts of published writes of all taken slots.
+    pub(crate) fn acked(&self) -> impl Iterator<Item = u64> + '_ {
+        self.acks.iter().filter_map(|slot| {
+            let val = slot.load(Ordering::SeqCst);
+            (val & ACK_TAKEN != 0).then_some(val & !ACK_TAKEN)
+        })
+    }
+
+    // check if header is initialized by the first one attached.
+    pub(crate) fn is_ready(&self) -> bool {
+        self.init.load(Ordering::SeqCst) == READY
+    }
+
+    // run closure only once for all attached Header of the same shared memory.
+    // other callers are blocked until the closure is finished. when the closure panics the
+    // state is restored and one of the blocked callers takes over. Error::InitStalled is
+    // returned when it's not finished in INIT_TIMEOUT.
+    pub(crate) fn init_once<F>(&self, func: F) -> Result<(), Error>
+    where
+        F: FnOnce(),
+    {
+        // restore UNINIT when closure unwinds.
+        struct Guard<'a>(&'a AtomicU8);
+
+        impl Drop for Guard<'_> {
+            fn drop(&mut self) {
+                self.0.store(UNINIT, Ordering::SeqCst);
+            }
+        }
+
+        let deadline = Instant::now() + INIT_TIMEOUT;
+        let mut func = Some(func);
+        loop {
+            match self
+                .init
+                .compare_exchange(UNINIT, BUSY, Ordering::SeqCst, Ordering::SeqCst)
+            {
+                Ok(_) => {
+                    let guard = Guard(&self.init);
+                    (func.take().unwrap())();
+                    mem::forget(guard);
+                    self.init.store(READY, Ordering::SeqCst);
+                    return Ok(());
+                }
+                Err(READY) => return Ok(()),
+                Err(_) if Instant::now() >= deadline => return Err(Error::InitStalled),
+                Err(_) => thread::yield_now(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::{atomic::AtomicBool, Arc};
+
+    #[test]
+    fn metrics_counter_at_end() {
+        // fields before it keep their offsets whether the counter is used or not.
+        assert_eq!(
+            mem::offset_of!(Header, total_writes) + mem::size_of::<AtomicU64>(),
+            mem::size_of::<Header>()
+        );
+    }
+
+    #[test]
+    fn interrupted_init() {
+        // SAFETY:
+        // Header is made of atomics and all zero is a valid state.
+        let header = unsafe { mem::zeroed::<Header>() };
+
+        // initializer panicked.
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            header.init_once(|| panic!("init failed"))
+        }));
+        assert!(res.is_err());
+        assert!(!header.is_ready());
+
+        let mut called = false;
+        header.init_once(|| called = true).unwrap();
+        assert!(called);
+        assert!(header.is_ready());
+        header.init_once(|| unreachable!()).unwrap();
+
+        // initializer died while busy.
+        header.init.store(BUSY, Ordering::SeqCst);
+        let now = Instant::now();
+        assert!(matches!(
+            header.init_once(|| unreachable!()),
+            Err(Error::InitStalled)
+        ));
+        assert!(now.elapsed() >= INIT_TIMEOUT);
+    }
+
+    #[test]
+    fn torn_meta() {
+        // SAFETY:
+        // Header is made of atomics and all zero is a valid state.
+        let header = Arc::new(unsafe { mem::zeroed::<Header>() });
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let readers = (0..4)
+            .map(|_| {
+                let header = header.clone();
+                let stop = stop.clone();
+                thread::spawn(move || {
+                    let mut reads = 0usize;
+                    while !stop.load(Ordering::SeqCst) {
+                        let (generation, sequence) = header.read_meta(|h| {
+                            (
+                                h.generation.load(Ordering::SeqCst),
+                                h.sequence.load(Ordering::SeqCst),
+                            )
+                        });
+                        assert_eq!(generation as u64 * 3, sequence);
+                        reads += 1;
+                    }
+                    reads
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for i in 1..100_000u32 {
+            header.write_meta(|h| {
+                h.generation.store(i, Ordering::SeqCst);
+                h.sequence.store(i as u64 * 3, Ordering::SeqCst);
+            });
+        }
+        stop.store(true, Ordering::SeqCst);
+
+        for reader in readers {
+            assert!(reader.join().unwrap() > 0);
+        }
+    }
+}
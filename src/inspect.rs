@@ -0,0 +1,170 @@
+use std::{mem, path::Path, sync::atomic::Ordering};
+
+use crate::{error::Error, header::Header, shared::Memory, shared_memory_open};
+
+/// Layout and state recorded in the header of a shared memory. Returned by [inspect].
+///
+/// It's a snapshot of fields read one by one so state fields (tick, watchers and so on) can
+/// be inconsistent with each other when the shared memory is accessed concurrently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SegmentHeader {
+    /// Size of shared memory in bytes.
+    pub len: usize,
+    /// Offset of value from the start of shared memory.
+    pub data_off: usize,
+    /// Size and alignment of the type shared memory is initialized for.
+    pub value_size: usize,
+    pub value_align: usize,
+    /// Width of tick counter in bytes.
+    pub tick_width: u8,
+    /// Id of [SyncStrategy](crate::SyncStrategy) guarding the value.
+    pub sync: u8,
+    /// See [Watcher::generation](crate::Watcher::generation).
+    pub generation: u32,
+    /// See [Watcher::sequence](crate::Watcher::sequence).
+    pub sequence: u64,
+    /// Tick of the last write.
+    pub tick: u8,
+    /// [Watched](crate::Watched) value is gone.
+    pub closed: bool,
+    /// A value has been written by current [Watched](crate::Watched).
+    pub written: bool,
+    /// Count of attached [Watcher](crate::Watcher).
+    pub watchers: u32,
+}
+
+/// Open the shared memory at given path and read it's header without a type of value.
+///
+/// Neither the lock is taken nor the value is read so it never blocks on writer and works
+/// for any `T`. Return [Error::Uninitialized] when the header is not initialized yet,
+/// [Error::MemoryTooSmall] when shared memory can't hold the header and
+/// [Error::InvalidLayout] when recorded layout is corrupt.
+pub fn inspect(path: impl AsRef<Path>) -> Result<SegmentHeader, Error> {
+    // size is ignored when opening.
+    let mem = shared_memory_open(path, 0)?;
+    read_header(&mem)
+}
+
+// size of shared memory required by the layout recorded in header. None when header is not
+// mapped as a whole or not initialized yet.
+pub(crate) fn recorded_size<M: Memory>(mem: &M) -> Option<usize> {
+    if mem.len() < mem::size_of::<Header>() {
+        return None;
+    }
+
+    // SAFETY:
+    // Memory is page aligned and large enough for Header. It's borrowed while Header is used.
+    let (header, _) = unsafe { Header::from_ptr(mem.as_ptr()) };
+    header.is_ready().then(|| {
+        header.data_off.load(Ordering::SeqCst) as usize
+            + header.value_size.load(Ordering::SeqCst) as usize
+    })
+}
+
+fn read_header<M: Memory>(mem: &M) -> Result<SegmentHeader, Error> {
+    let len = mem.len();
+    let header_size = mem::size_of::<Header>();
+    if len < header_size {
+        return Err(Error::MemoryTooSmall {
+            required: header_size,
+            actual: len,
+        });
+    }
+
+    // SAFETY:
+    // Memory is page aligned and large enough for Header. It's borrowed while Header is used.
+    let (header, _) = unsafe { Header::from_ptr(mem.as_ptr()) };
+    if !header.is_ready() {
+        return Err(Error::Uninitialized);
+    }
+
+    let data_off = header.data_off.load(Ordering::SeqCst) as usize;
+    let value_size = header.value_size.load(Ordering::SeqCst) as usize;
+    let value_align = header.value_align.load(Ordering::SeqCst) as usize;
+    if !value_align.is_power_of_two() {
+        return Err(Error::InvalidLayout {
+            reason: "value alignment is not a power of two",
+        });
+    }
+    if data_off < header_size {
+        return Err(Error::InvalidLayout {
+            reason: "data overlaps header",
+        });
+    }
+    // shared memory is page aligned so offset alone decides alignment.
+    if !data_off.is_multiple_of(value_align) {
+        return Err(Error::Misaligned { align: value_align });
+    }
+    if data_off + value_size > len {
+        return Err(Error::InvalidLayout {
+            reason: "data outside of shared memory",
+        });
+    }
+
+    let (generation, sequence) = header.read_meta(|header| {
+        (
+            header.generation.load(Ordering::SeqCst),
+            header.sequence.load(Ordering::SeqCst),
+        )
+    });
+    let tick = header.tick.load(Ordering::SeqCst);
+
+    Ok(SegmentHeader {
+        len,
+        data_off,
+        value_size,
+        value_align,
+        tick_width: header.tick_width.load(Ordering::SeqCst),
+        sync: header.sync.load(Ordering::SeqCst),
+        generation,
+        sequence,
+        tick: tick & !1,
+        closed: tick & 1 == 1,
+        written: header.written.load(Ordering::SeqCst) != 0,
+        watchers: header.watchers.load(Ordering::SeqCst),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{shared_memory_create, RwLockSync, SyncStrategy, Watched, Watcher};
+
+    #[test]
+    fn inspect_header() {
+        let path = "./test_file40";
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        assert!(matches!(read_header(&mem), Err(Error::Uninitialized)));
+
+        let watched = Watched::<u64>::new_from_mem(&mut mem);
+        watched.set_sequence(7);
+        let header = inspect(path).unwrap();
+        assert_eq!(header.value_size, 8);
+        assert_eq!(header.value_align, 8);
+        assert_eq!(header.sync, RwLockSync::ID);
+        assert_eq!(header.generation, 1);
+        assert_eq!(header.sequence, 7);
+        assert!(!header.written);
+        assert!(!header.closed);
+        assert_eq!(header.watchers, 0);
+
+        watched.write(1);
+        let mut mem2 = shared_memory_open(path, 1024).unwrap();
+        let _watcher = Watcher::<u64>::new_from_mem(&mut mem2);
+        let header = inspect(path).unwrap();
+        assert_eq!(header.tick, 2);
+        assert!(header.written);
+        assert_eq!(header.watchers, 1);
+
+        watched.0.header.value_align.store(3, Ordering::SeqCst);
+        assert!(matches!(inspect(path), Err(Error::InvalidLayout { .. })));
+        watched.0.header.value_align.store(8, Ordering::SeqCst);
+        watched.0.header.value_size.store(4096, Ordering::SeqCst);
+        assert!(matches!(inspect(path), Err(Error::InvalidLayout { .. })));
+        watched.0.header.value_size.store(8, Ordering::SeqCst);
+
+        drop(watched);
+        assert!(inspect(path).unwrap().closed);
+    }
+}
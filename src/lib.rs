@@ -2,15 +2,78 @@
 //! One watched process can write and update the snapshot of state.
 //! Multiple wwatcher processes can read the state and aware of state change.
 
+mod accumulate;
+#[cfg(unix)]
+mod anon;
+mod builder;
+mod cancel;
 mod error;
+mod fan_out;
+mod group;
+mod header;
+mod inspect;
+mod monitor;
+mod owned;
+mod pod;
+mod poll;
+mod registry;
+mod resilient;
 mod shared;
+mod string;
+mod sync;
 mod tick;
+mod trend;
+mod updates;
+#[cfg(feature = "poll-timer")]
+mod waker;
 
-use std::{mem, path::Path};
+#[cfg(unix)]
+pub use self::anon::AnonMem;
+pub use self::{
+    accumulate::{AccumulatingWatcher, Stats},
+    builder::WatchedBuilder,
+    cancel::CancelToken,
+    error::Error,
+    fan_out::FanOut,
+    group::{WatchedGroup, WatcherGroup},
+    inspect::{inspect, SegmentHeader},
+    owned::{OwnedWatched, OwnedWatcher},
+    pod::Pod,
+    poll::PollResult,
+    registry::Registry,
+    resilient::ResilientWatcher,
+    string::{WatchedString, WatcherString},
+    sync::{MutexSync, RwLockSync, SyncStrategy},
+    trend::{WatchedTrend, WatcherTrend},
+    updates::Updates,
+};
+
+use std::{
+    cell::{Cell, Ref, RefCell},
+    fs, mem,
+    path::{Path, PathBuf},
+    ptr,
+    sync::atomic::Ordering,
+    task::{Context, Poll},
+    thread,
+    time::{Duration, Instant},
+};
 
 use shared_memory::{Shmem, ShmemConf};
 
-use crate::{error::Error, shared::Shared};
+use crate::{
+    monitor::Monitor,
+    owned::OwnedMem,
+    shared::{Memory, Shared},
+    tick::{Tick, TickWindow},
+};
+
+// Interval of checking tick change in blocking waits.
+const WAIT_INTERVAL: Duration = Duration::from_millis(1);
+
+// Time and interval of retrying to open shared memory in observe.
+const OBSERVE_TIMEOUT: Duration = Duration::from_millis(500);
+const OBSERVE_INTERVAL: Duration = Duration::from_millis(10);
 
 /// Create shared memory with given path and size.
 /// *. If the path is already exist it would `OVERWRITE` the original file.
@@ -24,126 +87,2706 @@ pub fn shared_memory_create(path: impl AsRef<Path>, size: usize) -> Result<Shmem
     Ok(mem)
 }
 
+// check if file link at path is still pointing to shared memory with given os id.
+pub(crate) fn is_linked(path: &Path, os_id: &str) -> bool {
+    fs::read_to_string(path)
+        .map(|id| id == os_id)
+        .unwrap_or(false)
+}
+
 /// Open a shared memory with given path and size.
+///
+/// When the shared memory is initialized with a layout larger than the mapping, for example
+/// by a writer recreated it with a larger size, it's opened again with the size recorded in
+/// header. Error of opening again is returned as is. Platforms mapping the actual size of
+/// shared memory regardless of `size` never open it twice.
 pub fn shared_memory_open(path: impl AsRef<Path>, size: usize) -> Result<Shmem, Error> {
-    let mem = ShmemConf::new().size(size).flink(path.as_ref()).open()?;
-    Ok(mem)
+    let open = |size| ShmemConf::new().size(size).flink(path.as_ref()).open();
+    let mem = open(size)?;
+    match inspect::recorded_size(&mem) {
+        Some(required) if required > mem.len() => Ok(open(required)?),
+        _ => Ok(mem),
+    }
+}
+
+/// Open the shared memory at given path and construct an [OwnedWatcher] from it.
+///
+/// The size of shared memory is learned from the opened memory and it's validated for `T`.
+/// See [Watcher::try_from] for possible errors. When the shared memory is not created yet
+/// opening is retried every 10 milliseconds for up to 500 milliseconds and the last error is
+/// returned after.
+pub fn observe<T: Copy + Send + Sync>(path: impl AsRef<Path>) -> Result<OwnedWatcher<T>, Error> {
+    let deadline = Instant::now() + OBSERVE_TIMEOUT;
+    let mem = loop {
+        // size is ignored when opening.
+        match shared_memory_open(path.as_ref(), 0) {
+            Ok(mem) => break mem,
+            Err(e) if Instant::now() >= deadline => return Err(e),
+            Err(_) => thread::sleep(OBSERVE_INTERVAL),
+        }
+    };
+    OwnedWatcher::from_owned(mem)
+}
+
+/// Check a shared memory of given size can hold `T` with it's header and default lock.
+///
+/// Return [Error::MemoryTooSmall] when it's not large enough and [Error::InvalidLayout] when
+/// `T` is too large for offsets recorded in header. Useful for failing fast at startup.
+pub fn validate_layout<T: Copy + Send + Sync>(size: usize) -> Result<(), Error> {
+    Shared::<T, RwLockSync>::validate_layout(size)
 }
 
-pub struct Watched<'a, T: Copy>(Shared<'a, T>);
+/// Writer of a `T` shared between processes.
+///
+/// `T` is read by multiple watchers concurrently and written by the watched process
+/// through shared memory so it must be `Send + Sync` like it's shared between threads.
+/// Types with interior mutability or not thread safe are rejected.
+///
+/// `S` selects the lock type guarding value through [SyncStrategy] and is [RwLockSync] by
+/// default.
+///
+/// ```compile_fail
+/// # use std::cell::Cell;
+/// # use ipc_watcher::{shared_memory_create, Watched};
+/// let mut mem = shared_memory_create("./doc_cell", 64).unwrap();
+/// let watched = Watched::<&'static Cell<u8>>::new_from_mem(&mut mem);
+/// ```
+pub struct Watched<'a, T: Copy + Send + Sync, S: SyncStrategy = RwLockSync>(
+    Shared<'a, T, S>,
+    Local,
+    // shared memory owned by this value. dropped after other fields.
+    Option<OwnedMem>,
+);
 
-impl<T: Copy> Drop for Watched<'_, T> {
+// process local state of Watched.
+#[derive(Default)]
+struct Local {
+    monitors: RefCell<Vec<Monitor>>,
+    // tick is not advanced while paused. pending is set by write while paused.
+    paused: Cell<bool>,
+    pending: Cell<bool>,
+    // lock timeout of Watched::try_write. None is waiting forever.
+    timeout: Cell<Option<Duration>>,
+}
+
+impl<T: Copy + Send + Sync, S: SyncStrategy> Drop for Watched<'_, T, S> {
     fn drop(&mut self) {
+        // a write while paused is the final value. it's dropped only when the write lock is
+        // not obtained in time because drop can't panic.
+        let _ = self.publish_pending();
         self.0.tick.close();
+        #[cfg(feature = "poll-timer")]
+        waker::wake_all();
     }
 }
 
-impl<'a, T: Copy> Watched<'a, T> {
+impl<'a, T: Copy + Send + Sync, S: SyncStrategy> Watched<'a, T, S> {
     /// Construct a new watched value in given [Shmem].
+    /// The given [Shmem] can be newly created or already reserved by [Watcher::new_from_mem].
+    ///
+    /// # panics:
+    ///
+    /// When [Shmem] is not valid for `T`. See [Watched::try_from] for fallible construction.
     pub fn new_from_mem(mem: &'a mut Shmem) -> Self {
-        let shared = Shared::new_from_mem(mem);
-        shared.tick.store(0);
-        Self(shared)
+        Self::try_from(mem).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Construct a new watched value in given [AnonMem].
+    /// See [Watched::new_from_mem] and [AnonMem] for sharing it with child processes.
+    #[cfg(unix)]
+    pub fn new_from_anon(mem: &'a mut AnonMem) -> Self {
+        WatchedBuilder::new()
+            .build_anon(mem)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Construct a [WatchedBuilder] for extra configuration.
+    pub fn builder() -> WatchedBuilder<T, S> {
+        WatchedBuilder::new()
+    }
+
+    /// Alias of [Watched::new_from_mem].
+    #[deprecated(note = "use Watched::new_from_mem instead")]
+    pub fn new_in_shared(mem: &'a mut Shmem) -> Self {
+        Self::new_from_mem(mem)
     }
 
     /// Obtain a write lock and write a new `T` to the watchable value.
+    ///
+    /// # panics:
+    ///
+    /// When the lock is not obtained before the timeout set by [Watched::set_default_timeout].
+    /// The same applies to all other writes not taking a timeout or returning an error.
     pub fn write(&self, value: T) {
-        let mut guard = self.0.lock.lock().unwrap();
+        self.write_with_version(|_| value)
+    }
+
+    /// Same as [Watched::write] but only when `invariant` holds for `value`.
+    ///
+    /// Return [Error::InvariantViolated] when it doesn't. The value is not written and the
+    /// tick is not advanced so watchers never observe it.
+    pub fn write_guarded<F>(&self, value: T, invariant: F) -> Result<(), Error>
+    where
+        F: Fn(&T) -> bool,
+    {
+        if !invariant(&value) {
+            return Err(Error::InvariantViolated);
+        }
+        self.write(value);
+        Ok(())
+    }
+
+    /// Set the lock timeout used by every write of this value. Waiting is not bounded by
+    /// default.
+    ///
+    /// [Watched::try_write] returns [Error::TimedOut] when it elapsed. Writes without a way to
+    /// return an error like [Watched::write] panic instead. [Watched::write_timeout] takes a
+    /// timeout overriding it.
+    pub fn set_default_timeout(&self, timeout: Duration) {
+        self.1.timeout.set(Some(timeout));
+    }
+
+    /// Same as [Watched::write] but wait for the write lock only until the timeout set by
+    /// [Watched::set_default_timeout].
+    ///
+    /// Return [Error::TimedOut] when the lock is not obtained in time and the value is not
+    /// written.
+    pub fn try_write(&self, value: T) -> Result<(), Error> {
+        self.try_modify(self.1.timeout.get(), |_, val| *val = value)
+    }
+
+    /// Same as [Watched::try_write] with `timeout` taking precedence over the default one.
+    pub fn write_timeout(&self, value: T, timeout: Duration) -> Result<(), Error> {
+        self.try_modify(Some(timeout), |_, val| *val = value)
+    }
+
+    /// Same as [Watched::write] and call `after` with the tick of written value after
+    /// write lock is released.
+    pub fn write_with_callback<F>(&self, value: T, after: F)
+    where
+        F: FnOnce(u8),
+    {
+        let mut tick = 0;
+        self.write_with_version(|t| {
+            tick = t;
+            value
+        });
+        after(tick);
+    }
+
+    /// Reconstruct the lock in shared memory. The lock state is reset for all processes.
+    ///
+    /// This is a last resort for recovering from a lock left in bad state. For example when a
+    /// process died while holding it.
+    ///
+    /// # Safety
+    ///
+    /// No [Watched] or [Watcher] in any process can be holding or waiting for the lock.
+    pub unsafe fn reinit_lock(&mut self) {
+        self.0.reinit_lock();
+    }
+
+    /// Convert to a [Watcher] of the same shared memory without closing the value.
+    ///
+    /// Other watchers keep observing the value as alive and a new [Watched] can take over
+    /// the shared memory later. The returned watcher treats the current value as observed.
+    ///
+    /// Ownership of the shared memory is held by [Shmem] and not changed by this conversion.
+    /// An owner [Shmem] still removes the shared memory on drop. Use [Shmem::set_owner]
+    /// before constructing [Watched] when the ownership should be handed over.
+    ///
+    /// A write while [Watched::pause] is published before conversion.
+    ///
+    /// # panics:
+    ///
+    /// Same as [Watched::resume] when there is a write to publish.
+    pub fn into_watcher(self) -> Watcher<'a, T, S> {
+        self.publish_pending().unwrap_or_else(|e| panic!("{}", e));
+        let this = mem::ManuallyDrop::new(self);
+        // SAFETY:
+        // Watched is never used or dropped after fields are moved out.
+        let (shared, local, mem) =
+            unsafe { (ptr::read(&this.0), ptr::read(&this.1), ptr::read(&this.2)) };
+        drop(local);
+        let mut watcher = Watcher::from_shared(shared, None);
+        watcher.tick = watcher.shared.tick.try_get().unwrap_or(0);
+        watcher.recent.push(watcher.tick);
+        watcher.ack();
+        watcher.mem = mem;
+        watcher
+    }
+
+    /// Check the offsets of tick, lock and data recorded in shared memory are internally
+    /// consistent, within the shared memory and properly aligned for `T`.
+    ///
+    /// Return the first detected issue. See [Error] for possible ones.
+    pub fn self_check(&self) -> Result<(), Error> {
+        self.0.self_check()
+    }
+
+    /// Touch every page of shared memory so following writes don't page fault.
+    ///
+    /// It forces physical memory to be allocated for the whole shared memory up front and
+    /// increase resident memory at startup. Pages can still be swapped out later unless
+    /// they are locked by other means.
+    pub fn prefault(&self) {
+        self.0.prefault();
+    }
+
+    /// Make every watcher report a change on it's next observe even when the value is not
+    /// written. Useful after reloading writer side state with identical values.
+    ///
+    /// The tick is not advanced so it's not counted by [Watcher::overruns]. It's not a new
+    /// [Watched] value either so [Watcher::generation] and [Watcher::generation_changed] are
+    /// not affected.
+    pub fn force_resync(&self) {
+        self.0.header.resync.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Block current thread until every registered [Watcher] acknowledged the write of `tick`
+    /// or a later one with [Watcher::ack]. Tick values are the ones given by
+    /// [Watched::write_with_version]. The tick is wrapping so it must be one of the last 128
+    /// writes. An older tick is taken as a more recent write with the same tick value. A tick
+    /// not written since the shared memory is created is treated as acknowledged.
+    ///
+    /// A watcher is registered with the writes published before it's construction treated as
+    /// acknowledged. Later writes are only acknowledged after they are read.
+    ///
+    /// A watcher is registered from construction to drop so a watcher detached in the middle
+    /// of waiting no longer blocks it. A watcher in a process that crashed is never
+    /// unregistered and blocks it until timeout. Up to 32 watchers can be registered and
+    /// watchers attached after are not waited for.
+    ///
+    /// Return [Error::TimedOut] when some watcher didn't acknowledge before `timeout`.
+    pub fn wait_for_all_acks(&self, tick: u8, timeout: Duration) -> Result<(), Error> {
+        // tick and published count are only changed by this value so they are consistent.
+        let (current, _) = self.0.tick.load();
+        let published = self.0.header.published.load(Ordering::SeqCst);
+        let target = if self.1.pending.get() && tick == self.0.tick.next() {
+            // write while paused is not published yet.
+            published + 1
+        } else {
+            match published.checked_sub(Tick::distance(tick, current) as u64) {
+                Some(target) => target,
+                None => return Ok(()),
+            }
+        };
+        self.wait_for_acks(target, timeout)
+    }
+
+    // block until every registered watcher acknowledged given count of published writes.
+    fn wait_for_acks(&self, target: u64, timeout: Duration) -> Result<(), Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.0.header.acked().all(|acked| acked >= target) {
+                return Ok(());
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::TimedOut);
+            }
+
+            thread::sleep(WAIT_INTERVAL.min(deadline - now));
+        }
+    }
+
+    /// Write a new `T` and block current thread until every registered [Watcher] acknowledged
+    /// it with [Watcher::ack]. A watcher reading and acknowledging values forms a synchronous
+    /// request and response with this method.
+    ///
+    /// All registered watchers are waited for. Not just the responding one. See
+    /// [Watched::wait_for_all_acks] for registration and errors. The value stays written when
+    /// [Error::TimedOut] returns. While [Watched::pause] is in effect the tick is not
+    /// advanced so it always times out.
+    pub fn write_and_wait_ack(&self, value: T, timeout: Duration) -> Result<(), Error> {
+        self.write(value);
+        // a write while paused is published later with the next count.
+        let mut target = self.0.header.published.load(Ordering::SeqCst);
+        if self.1.pending.get() {
+            target += 1;
+        }
+        self.wait_for_acks(target, timeout)
+    }
+
+    /// Check if `other` is attached to the same shared memory as this value.
+    ///
+    /// Shared memory created or opened through [Shmem] is compared by it's os id. That is the
+    /// name of shared memory object on unix and the name of file mapping on windows. Other
+    /// memory like [AnonMem] is compared by the address it's mapped at in current process.
+    pub fn is_same_segment(&self, other: &Self) -> bool {
+        self.0.is_same(&other.0)
+    }
+
+    /// Count of [Watcher] currently attached to the shared memory in all processes.
+    ///
+    /// A watcher is counted from construction to drop. A watcher in a process that crashed
+    /// is never uncounted.
+    pub fn watcher_count(&self) -> usize {
+        self.0.header.watchers.load(Ordering::SeqCst) as usize
+    }
+
+    /// Spawn a monitor thread calling `func` with the new count whenever [Watched::watcher_count]
+    /// changed. Useful for starting and stopping expensive production on demand.
+    ///
+    /// The count is checked in an interval of 10 milliseconds so changes happened in between
+    /// are coalesced into one call. The monitor thread is stopped and joined when this value
+    /// is dropped.
+    pub fn on_watcher_count_change<F>(&self, func: F)
+    where
+        F: FnMut(usize) + Send + 'static,
+    {
+        // SAFETY:
+        // Monitor is owned by Watched and dropped before the shared memory borrow ends.
+        let monitor = unsafe { Monitor::spawn(&self.0.header.watchers, func) };
+        self.1.monitors.borrow_mut().push(monitor);
+    }
+
+    /// Count of writes to the shared memory since it's created. Including the ones by previous
+    /// [Watched] values and the ones while paused.
+    ///
+    /// Unlike the tick it never wraps in practice. A u64 counter written a billion times per
+    /// second overflows after more than 500 years. Writes of a [Watched] built without the
+    /// `metrics` feature are not counted.
+    #[cfg(feature = "metrics")]
+    pub fn total_writes(&self) -> u64 {
+        self.0.header.total_writes.load(Ordering::SeqCst)
+    }
+
+    /// Set the application defined sequence number observed by [Watcher::sequence].
+    ///
+    /// Sequence is independent from the tick used for change detection. Setting it does not
+    /// notify watchers and writing value does not change it.
+    pub fn set_sequence(&self, seq: u64) {
+        self.0
+            .header
+            .write_meta(|header| header.sequence.store(seq, Ordering::SeqCst));
+    }
+
+    /// Current sequence number set by [Watched::set_sequence].
+    pub fn sequence(&self) -> u64 {
+        self.0
+            .header
+            .read_meta(|header| header.sequence.load(Ordering::SeqCst))
+    }
+
+    /// Obtain a write lock and write a new `T` constructed from the tick value watchers would
+    /// observe along with it.
+    ///
+    /// Closure is called with write lock held so the given tick always match the tick of the
+    /// value written.
+    pub fn write_with_version<F>(&self, func: F)
+    where
+        F: FnOnce(u8) -> T,
+    {
+        self.modify(|tick, val| *val = func(tick));
+    }
+
+    // obtain a write lock and modify T in place with the tick value watchers would observe.
+    pub(crate) fn modify<F>(&self, func: F)
+    where
+        F: FnOnce(u8, &mut T),
+    {
+        self.try_modify(self.1.timeout.get(), func)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // same as modify and give up when write lock is not obtained until timeout.
+    fn try_modify<F>(&self, timeout: Option<Duration>, func: F) -> Result<(), Error>
+    where
+        F: FnOnce(u8, &mut T),
+    {
+        let mut guard = self.0.try_lock(timeout).ok_or(Error::TimedOut)?;
 
         // SAFETY:
         // This cast is safe. Watcher<T> type is the only type constructor expose.
-        let val = unsafe { mem::transmute::<_, &mut T>(&mut **guard) };
-        *val = value;
+        let val = unsafe { mem::transmute::<&mut u8, &mut T>(&mut **guard) };
+        func(self.0.tick.next(), val);
+        // generation is only changed by a new Watched so it's the one of this value.
+        let generation = self.0.header.generation.load(Ordering::SeqCst);
+        self.0
+            .header
+            .value_generation
+            .store(generation, Ordering::SeqCst);
+
+        #[cfg(feature = "metrics")]
+        self.0.header.total_writes.fetch_add(1, Ordering::SeqCst);
+
+        if self.1.paused.get() {
+            self.1.pending.set(true);
+        } else {
+            self.publish();
+        }
+        Ok(())
+    }
 
+    // advance tick and notify watchers.
+    fn publish(&self) {
+        self.0.header.published.fetch_add(1, Ordering::SeqCst);
         self.0.tick.tick();
+        if self.0.header.written.load(Ordering::Relaxed) == 0 {
+            self.0.header.written.store(1, Ordering::SeqCst);
+        }
+        #[cfg(feature = "poll-timer")]
+        waker::wake_all();
+    }
+
+    /// Obtain a write lock and replace the raw bytes of value with `bytes`. Watchers are
+    /// notified once. Useful for restoring a snapshot taken by [Watcher::save_snapshot].
+    ///
+    /// Return [Error::SnapshotSize] when length of `bytes` is not the size of `T`.
+    pub fn load_snapshot(&self, bytes: &[u8]) -> Result<(), Error>
+    where
+        T: Pod,
+    {
+        if bytes.len() != mem::size_of::<T>() {
+            return Err(Error::SnapshotSize {
+                expected: mem::size_of::<T>(),
+                found: bytes.len(),
+            });
+        }
+
+        self.modify(|_, val| {
+            // SAFETY:
+            // Length is checked and any bytes are valid for Pod.
+            unsafe {
+                ptr::copy_nonoverlapping(bytes.as_ptr(), val as *mut T as *mut u8, bytes.len())
+            }
+        });
+        Ok(())
+    }
+
+    /// Stop notifying watchers of writes until [Watched::resume].
+    ///
+    /// Writes while paused still update the value in place but don't advance the tick. So
+    /// watchers observe no change in between and are notified of the post-resume state once.
+    /// [Watcher::read] in the meantime is not prevented and can see the intermediate value.
+    ///
+    /// A write while paused is also published by [Watched::into_watcher] and on drop. So
+    /// the final value is never lost. Drop gives up publishing it only when the write lock is
+    /// not obtained before the timeout set by [Watched::set_default_timeout].
+    pub fn pause(&self) {
+        self.1.paused.set(true);
+    }
+
+    /// Resume notifying watchers after [Watched::pause]. Tick is advanced once under the write
+    /// lock when any write happened while paused.
+    ///
+    /// # panics:
+    ///
+    /// Same as [Watched::write] when there is a write to publish.
+    pub fn resume(&self) {
+        self.1.paused.set(false);
+        self.publish_pending().unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    // publish a write while paused under the write lock. no op when there is none.
+    fn publish_pending(&self) -> Result<(), Error> {
+        if self.1.pending.get() {
+            let _guard = self
+                .0
+                .try_lock(self.1.timeout.get())
+                .ok_or(Error::TimedOut)?;
+            self.1.pending.set(false);
+            self.publish();
+        }
+        Ok(())
+    }
+}
+
+/// Construct a new watched value in given [Shmem].
+/// See [Watched::new_from_mem].
+///
+/// The first one attached to the shared memory records layout of `T` in header. Every
+/// construction, including the first, validates the shared memory in this order:
+///
+/// - [Error::MemoryTooSmall] when [Shmem] is not large enough for header, lock and `T`.
+/// - [Error::InitStalled] when header is being initialized by another one not finishing in
+///   time.
+/// - [Error::TickWidthMismatch] when header records a tick counter of different width.
+/// - [Error::SyncMismatch] when header records a different [SyncStrategy] than `S`.
+/// - [Error::LayoutMismatch] when header records a `T` of different size or alignment.
+/// - [Error::InvalidLayout] when data offset recorded in header overlaps the lock.
+/// - [Error::MemoryTooSmall] when [Shmem] is not large enough for `T` at the recorded data
+///   offset.
+/// - [Error::Misaligned] when the recorded data offset is not aligned to `T`.
+impl<'a, T: Copy + Send + Sync, S: SyncStrategy> TryFrom<&'a mut Shmem> for Watched<'a, T, S> {
+    type Error = Error;
+
+    fn try_from(mem: &'a mut Shmem) -> Result<Self, Self::Error> {
+        WatchedBuilder::new().build(mem)
     }
 }
 
-pub struct Watcher<'a, T: Copy> {
+/// Reader of a `T` shared between processes.
+///
+/// Like [Watched], `T` must be `Send + Sync` and `S` is the [SyncStrategy] of value.
+///
+/// ```compile_fail
+/// # use ipc_watcher::{shared_memory_open, Watcher};
+/// let mut mem = shared_memory_open("./doc_ptr", 64).unwrap();
+/// let watcher = Watcher::<*const u8>::new_from_mem(&mut mem);
+/// ```
+pub struct Watcher<'a, T: Copy + Send + Sync, S: SyncStrategy = RwLockSync> {
     tick: u8,
-    shared: Shared<'a, T>,
+    // tick at construction. writes before it are never observable so overruns of the first
+    // observed change are counted from it. None after the first change or a new generation.
+    attach_tick: Option<u8>,
+    overruns: usize,
+    first_overrun: Option<u8>,
+    recent: TickWindow,
+    out_of_order: bool,
+    cache: Option<T>,
+    // recorded by reads taking &self too.
+    last_error: RefCell<Option<Error>>,
+    generation: u32,
+    // generation observed ticks belong to. ticks restart from 0 with a new generation.
+    tick_generation: u32,
+    value_generation: Cell<u32>,
+    resync: u32,
+    // count of published writes of the last read value. acknowledged by Watcher::ack.
+    read_published: Cell<u64>,
+    // index of ack cursor in header.
+    ack_slot: Option<usize>,
+    // lock timeout of Watcher::try_read. None is waiting forever.
+    timeout: Option<Duration>,
+    // file link path and os id of Shmem.
+    link: Option<(PathBuf, String)>,
+    shared: Shared<'a, T, S>,
+    // shared memory owned by this watcher. dropped after other fields.
+    mem: Option<OwnedMem>,
 }
 
-impl<'a, T: Copy> Watcher<'a, T> {
+impl<'a, T: Copy + Send + Sync, S: SyncStrategy> Watcher<'a, T, S> {
     /// Construct a new observer from given [Shmem].
-    /// The given [Shmem] must contain an already initialized [Watched] value.
+    /// The given [Shmem] can contain an already initialized [Watched] value or be newly
+    /// created. In the later case the shared memory is reserved for a [Watched] value
+    /// constructed later and no change is observed until the first write.
+    ///
+    /// # panics:
+    ///
+    /// When [Shmem] is not valid for `T`. See [Watcher::try_from] for fallible construction.
     pub fn new_from_mem(mem: &'a mut Shmem) -> Self {
-        let shared = Shared::exist_from_mem(mem);
-        Watcher { tick: 0, shared }
+        Self::try_from(mem).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Construct a new observer from given [AnonMem].
+    /// See [Watcher::new_from_mem] and [AnonMem] for sharing it with parent process.
+    #[cfg(unix)]
+    pub fn new_from_anon(mem: &'a mut AnonMem) -> Self {
+        Self::try_from_memory(mem, None).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_from_memory<M: Memory>(
+        mem: &'a M,
+        link: Option<(PathBuf, String)>,
+    ) -> Result<Self, Error> {
+        Shared::try_from_mem(mem).map(|shared| Self::from_shared(shared, link))
+    }
+
+    fn from_shared(shared: Shared<'a, T, S>, link: Option<(PathBuf, String)>) -> Self {
+        let (generation, (attach_tick, _)) = shared
+            .header
+            .read_meta(|header| (header.generation.load(Ordering::SeqCst), shared.tick.load()));
+        let resync = shared.header.resync.load(Ordering::SeqCst);
+        shared.header.watchers.fetch_add(1, Ordering::SeqCst);
+        // writes published before construction are treated as acknowledged. a write in
+        // progress is published after and not counted.
+        let published = shared.header.published.load(Ordering::SeqCst);
+        let ack_slot = shared.header.register_ack(published);
+        Watcher {
+            tick: 0,
+            attach_tick: Some(attach_tick),
+            overruns: 0,
+            first_overrun: None,
+            recent: TickWindow::new(),
+            out_of_order: false,
+            cache: None,
+            last_error: RefCell::new(None),
+            generation,
+            tick_generation: generation,
+            value_generation: Cell::new(0),
+            resync,
+            read_published: Cell::new(published),
+            ack_slot,
+            timeout: None,
+            link,
+            shared,
+            mem: None,
+        }
+    }
+
+    /// Alias of [Watcher::new_from_mem].
+    #[deprecated(note = "use Watcher::new_from_mem instead")]
+    pub fn new_in_shared(mem: &'a mut Shmem) -> Self {
+        Self::new_from_mem(mem)
     }
 
     /// Obtain a read lock and access &T through a closure.
     /// Closure is expected to be non blocking and kept as shortest in execution time as possible.
+    ///
+    /// The lock is released by it's guard when closure panics so it's not left held for
+    /// other processes.
+    ///
+    /// # panics:
+    ///
+    /// When the lock is not obtained before the timeout set by
+    /// [Watcher::set_default_timeout]. The same applies to all other reads not taking a
+    /// timeout or returning an error.
     pub fn read<F, O>(&self, func: F) -> O
     where
         F: FnOnce(&T) -> O,
     {
-        let guard = self.shared.lock.rlock().unwrap();
-        let val = unsafe { mem::transmute::<_, &T>(&**guard) };
-        func(val)
+        self.try_read(func).unwrap_or_else(|e| panic!("{}", e))
     }
 
-    /// Observe the value change of T.
-    /// [Watcher::read] method is expected to be called immediately when true returns.
-    ///
-    /// # panics:
+    /// Check if `other` is attached to the same shared memory as this watcher.
     ///
-    /// When [Watched] value is gone. Happen when the process owning [Watched] decide to
-    /// destroy the value.
-    pub fn has_changed(&mut self) -> bool {
-        let tick_new = self.shared.tick.try_get().expect("Watched value is gone");
-        if tick_new != self.tick {
-            self.tick = tick_new;
-            true
-        } else {
-            false
-        }
+    /// Shared memory created or opened through [Shmem] is compared by it's os id. That is the
+    /// name of shared memory object on unix and the name of file mapping on windows. Other
+    /// memory like [AnonMem] is compared by the address it's mapped at in current process.
+    pub fn is_same_segment(&self, other: &Self) -> bool {
+        self.shared.is_same(&other.shared)
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
+    /// Set the lock timeout used by every read of this watcher. Waiting is not bounded by
+    /// default.
+    ///
+    /// [Watcher::try_read] returns [Error::TimedOut] when it elapsed. Reads without a way to
+    /// return an error like [Watcher::read] panic instead. [Watcher::read_timeout] takes a
+    /// timeout overriding it.
+    pub fn set_default_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+    }
 
-    #[repr(C)]
-    #[derive(Clone, Copy, Debug)]
-    struct Foo([u8; 512]);
+    /// Same as [Watcher::read] but wait for the read lock only until the timeout set by
+    /// [Watcher::set_default_timeout].
+    ///
+    /// Return [Error::TimedOut] when the lock is not obtained in time and closure is not
+    /// called.
+    pub fn try_read<F, O>(&self, func: F) -> Result<O, Error>
+    where
+        F: FnOnce(&T) -> O,
+    {
+        self.read_timeout_inner(self.timeout, func)
+    }
 
-    #[test]
-    fn works() {
-        let mut mem = shared_memory_create("./test_file", 24).unwrap();
+    /// Same as [Watcher::try_read] with `timeout` taking precedence over the default one.
+    pub fn read_timeout<F, O>(&self, timeout: Duration, func: F) -> Result<O, Error>
+    where
+        F: FnOnce(&T) -> O,
+    {
+        self.read_timeout_inner(Some(timeout), func)
+    }
 
-        let watched = Watched::<Foo>::new_from_mem(&mut mem);
+    fn read_timeout_inner<F, O>(&self, timeout: Option<Duration>, func: F) -> Result<O, Error>
+    where
+        F: FnOnce(&T) -> O,
+    {
+        self.shared
+            .try_read(timeout, |val| {
+                self.record_generation();
+                func(val)
+            })
+            .ok_or_else(|| {
+                self.record_error(Error::TimedOut);
+                Error::TimedOut
+            })
+    }
 
-        watched.write(Foo([123; 512]));
+    /// Obtain a read lock and copy `T` when a value is published by current [Watched]. Return
+    /// `T::default()` otherwise without touching the value in shared memory.
+    ///
+    /// A write while [Watched::pause] is not published until resumed.
+    pub fn value_or_default(&self) -> T
+    where
+        T: Default,
+    {
+        self.read(|val| {
+            // written flag is set with write lock held.
+            if self.shared.header.written.load(Ordering::SeqCst) != 0 {
+                *val
+            } else {
+                T::default()
+            }
+        })
+    }
 
-        std::thread::spawn(|| {
-            let mut mem = shared_memory_open("./test_file", 24).unwrap();
+    /// Obtain a read lock and copy `T` along with the generation of [Watched] value wrote it and
+    /// the current tick.
+    ///
+    /// All three are read with the lock held. Writes and construction of a new [Watched]
+    /// value change them with write lock held so they are always consistent with each other.
+    /// A value left by a previous [Watched] returns it's generation which is less than
+    /// [Watcher::generation]. The tick restarts with the new [Watched] so it's 0 until the
+    /// first write of it. Generation is 0 when nothing is ever written.
+    pub fn read_versioned(&self) -> (T, u32, u8) {
+        self.read(|val| {
+            let generation = self.shared.header.value_generation.load(Ordering::SeqCst);
+            (*val, generation, self.shared.tick.load().0)
+        })
+    }
 
-            let mut watcher = Watcher::<Foo>::new_from_mem(&mut mem);
+    /// Generation of [Watched] value wrote the value of last read of this watcher. 0 before
+    /// any read or when nothing is ever written.
+    ///
+    /// Generation advances every time a new [Watched] value is constructed in the shared
+    /// memory. Including a restarted writer reopening the same shared memory. A value
+    /// read with a generation less than [Watcher::generation] is produced by a previous
+    /// writer. For example a value left by a crashed writer and read before the restarted
+    /// one writes.
+    pub fn value_generation(&self) -> u32 {
+        self.value_generation.get()
+    }
 
-            assert!(watcher.has_changed());
+    // record generation and published count of value being read with read lock held.
+    fn record_generation(&self) -> u32 {
+        let generation = self.shared.header.value_generation.load(Ordering::SeqCst);
+        self.value_generation.set(generation);
+        self.read_published
+            .set(self.shared.header.published.load(Ordering::SeqCst));
+        generation
+    }
 
-            watcher.read(|foo| assert_eq!(foo.0.len(), 512));
+    /// Obtain a read lock and copy the raw bytes of value. See [Watched::load_snapshot].
+    pub fn save_snapshot(&self) -> Vec<u8>
+    where
+        T: Pod,
+    {
+        self.read(|val| {
+            // SAFETY:
+            // Pod has no padding so all bytes of it are initialized.
+            let bytes = unsafe {
+                std::slice::from_raw_parts(val as *const T as *const u8, mem::size_of::<T>())
+            };
+            bytes.to_vec()
         })
-        .join()
-        .unwrap();
     }
 
-    #[test]
-    #[should_panic]
-    fn drop_watched() {
-        let mut mem = shared_memory_create("./test_file2", 24).unwrap();
+    /// Count of writes to the shared memory since it's created. See [Watched::total_writes].
+    ///
+    /// It's read without the lock and can be sampled over time to compute the write rate.
+    #[cfg(feature = "metrics")]
+    pub fn observed_total(&self) -> u64 {
+        self.shared.header.total_writes.load(Ordering::SeqCst)
+    }
 
-        let watched = Watched::<Foo>::new_from_mem(&mut mem);
+    /// Acknowledge the last value read by this watcher to [Watched::wait_for_all_acks]. A
+    /// change observed but not read is not acknowledged.
+    ///
+    /// No op when this watcher is not registered because all ack slots were taken.
+    pub fn ack(&self) {
+        if let Some(slot) = self.ack_slot {
+            self.shared.header.ack(slot, self.read_published.get());
+        }
+    }
 
-        drop(watched);
+    /// Same as [Watcher::read] but never block on the lock.
+    ///
+    /// The read lock is tried without blocking up to `max_retries` more times when it's held
+    /// by writer. Return [Error::TooContended] when the retry budget is exhausted so caller
+    /// can back off or fall back to [Watcher::read].
+    pub fn read_bounded<F, O>(&self, max_retries: u32, func: F) -> Result<O, Error>
+    where
+        F: FnOnce(&T) -> O,
+    {
+        let mut func = Some(func);
+        for _ in 0..=max_retries {
+            // zero timeout fails immediately when lock is not available.
+            let out = self.shared.try_read(Some(Duration::ZERO), |val| {
+                self.record_generation();
+                (func.take().unwrap())(val)
+            });
+            if let Some(out) = out {
+                return Ok(out);
+            }
+            thread::yield_now();
+        }
+        self.record_error(Error::TooContended);
+        Err(Error::TooContended)
+    }
 
-        let mut mem = shared_memory_open("./test_file2", 24).unwrap();
+    /// Reconstruct the lock in shared memory. See [Watched::reinit_lock].
+    ///
+    /// # Safety
+    ///
+    /// No [Watched] or [Watcher] in any process can be holding or waiting for the lock.
+    pub unsafe fn reinit_lock(&mut self) {
+        self.shared.reinit_lock();
+    }
 
-        let mut watcher = Watcher::<Foo>::new_from_mem(&mut mem);
+    /// Generation of [Watched] value. It's increased every time a new [Watched] value is
+    /// constructed in the same shared memory and starts from 1.
+    pub fn generation(&self) -> u32 {
+        self.shared
+            .header
+            .read_meta(|header| header.generation.load(Ordering::SeqCst))
+    }
 
-        watcher.has_changed();
+    /// Return true when a new [Watched] value took over the shared memory since construction
+    /// of this watcher or the last call of this method.
+    ///
+    /// Tick of the new [Watched] value restarts counting so it's not comparable to the
+    /// observed ticks. Observing change re-syncs this watcher to the new [Watched] whether
+    /// this method is called or not. The reset is neither reported as change nor counted by
+    /// [Watcher::overruns] and the value of the new [Watched] is reported by
+    /// [Watcher::has_changed] when it's written.
+    pub fn generation_changed(&mut self) -> bool {
+        let generation = self.generation();
+        if generation == self.generation {
+            return false;
+        }
+
+        self.generation = generation;
+        true
+    }
+
+    /// Sequence number set by [Watched::set_sequence].
+    pub fn sequence(&self) -> u64 {
+        self.shared
+            .header
+            .read_meta(|header| header.sequence.load(Ordering::SeqCst))
+    }
+
+    /// Obtain a read lock and access the raw pointer of `T` in shared memory through a closure.
+    /// It's for processing large `T` in place with foreign code without copying.
+    ///
+    /// # Safety
+    ///
+    /// The pointer is only valid in the closure while read lock is held. It must not be
+    /// stored or used after the closure returns. Data behind the pointer must not be written
+    /// and no `&mut` can be created from it.
+    pub unsafe fn read_ptr<F, O>(&self, func: F) -> O
+    where
+        F: FnOnce(*const T) -> O,
+    {
+        self.read(|val| func(val))
+    }
+
+    /// Return true when [Watched] value is gone or the shared memory is no longer reachable
+    /// from it's file link. The value observed afterwards should not be trusted.
+    ///
+    /// Shared memory stays mapped for this watcher after [Watched] dropped it or it's unlinked.
+    /// On Linux and macOS the memory is valid until unmapped. On Windows it's valid while any
+    /// process still holds it. In both cases the last value can still be read but it's
+    /// never updated.
+    ///
+    /// The file link is checked when [Watcher] is constructed from a [Shmem] with file link.
+    /// A missing file link or one pointing to a different shared memory (Replaced by a new
+    /// [Watched]) means the writer is gone.
+    pub fn writer_gone(&self) -> bool {
+        if self.shared.tick.try_get().is_none() {
+            return true;
+        }
+
+        match self.link {
+            Some((ref path, ref os_id)) => !is_linked(path, os_id),
+            None => false,
+        }
+    }
+
+    /// Access a local copy of `T` without taking read lock when value of T is not changed.
+    ///
+    /// The tick is checked on every call and the local copy is refreshed with read lock only
+    /// when it advanced. So the returned value is only stale when [Watched] write after the
+    /// check and it's refreshed on the next call. Change observed by this method is not
+    /// reported by [Watcher::has_changed] again.
+    ///
+    /// When [Watched] value is gone the last copy is returned.
+    pub fn read_cached(&mut self) -> &T {
+        let changed = self.try_has_changed().unwrap_or_else(|e| {
+            self.record_error(e);
+            false
+        });
+        if changed || self.cache.is_none() {
+            self.cache = Some(self.read(|val| *val));
+        }
+        self.cache.as_ref().unwrap()
+    }
+
+    /// Observe the value change of T.
+    /// [Watcher::read] method is expected to be called immediately when true returns.
+    ///
+    /// The final value written before [Watched] value is gone is still reported as change.
+    ///
+    /// # panics:
+    ///
+    /// When [Watched] value is gone. Happen when the process owning [Watched] decide to
+    /// destroy the value.
+    pub fn has_changed(&mut self) -> bool {
+        self.try_has_changed().expect("Watched value is gone")
+    }
+
+    /// Block current thread until value of T changed.
+    /// [Watcher::read] method is expected to be called immediately when `Ok` returns.
+    ///
+    /// Return [Error::Closed] when [Watched] value is gone.
+    pub fn wait_for_change(&mut self) -> Result<(), Error> {
+        self.wait(None)
+    }
+
+    /// Same as [Watcher::wait_for_change] but can be cancelled by [CancelToken::cancel] from
+    /// other thread and return [Error::Cancelled].
+    ///
+    /// The tick and token are checked in an interval of 1 millisecond.
+    pub fn wait_for_change_cancellable(&mut self, token: &CancelToken) -> Result<(), Error> {
+        self.wait(Some(token))
+    }
+
+    /// Block current thread until the tick of value reach or pass `target`.
+    /// Tick values are the ones given by [Watched::write_with_version].
+    ///
+    /// The tick is a wrapping counter so `target` is compared in wrapping order. Return
+    /// immediately when `target` is already passed, that is it's behind the current tick by
+    /// less than half of the counter range. Otherwise it's waited for.
+    /// Return [Error::Closed] when [Watched] value is gone before `target` is reached.
+    pub fn wait_for_tick(&mut self, target: u8) -> Result<(), Error> {
+        loop {
+            // a target reached before the value is gone is not an error.
+            let observed = self.try_has_changed();
+            if Tick::reached(self.tick, target) {
+                return Ok(());
+            }
+            observed?;
+
+            thread::sleep(WAIT_INTERVAL);
+        }
+    }
+
+    /// Poll for value change of T from an async executor without blocking.
+    ///
+    /// Return `Ready(Ok(()))` when value changed and `Ready(Err(Error::Closed))` when
+    /// [Watched] value is gone.
+    ///
+    /// Shared memory can't notify a waker from another process. When nothing changed the
+    /// waker of `cx` is woken before `Pending` returns so the executor polls again as soon as
+    /// it can. No thread is spawned and nothing is allocated but the task is effectively
+    /// busy polling. Executors should yield or sleep between polls when it's a concern.
+    ///
+    /// With the `poll-timer` feature the waker is registered instead and woken by a timer
+    /// thread every millisecond. The thread is spawned on the first registration and lives
+    /// for the rest of the process. A write of [Watched] in current process wakes registered
+    /// wakers right away.
+    pub fn poll_change(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        match self.try_has_changed() {
+            Ok(true) => Poll::Ready(Ok(())),
+            #[cfg(not(feature = "poll-timer"))]
+            Ok(false) => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            #[cfg(feature = "poll-timer")]
+            Ok(false) => {
+                waker::register(cx.waker());
+                // a change published before registered is not missed.
+                match self.try_has_changed() {
+                    Ok(false) => Poll::Pending,
+                    Ok(true) => Poll::Ready(Ok(())),
+                    Err(e) => Poll::Ready(Err(e)),
+                }
+            }
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+
+    /// Block current thread until the first value is written by [Watched] or `timeout`
+    /// elapsed. It's for watchers started before the writer.
+    ///
+    /// Return [Error::Closed] when [Watched] value is gone. Including the case it's
+    /// constructed, written and dropped before this call. Return [Error::TimedOut] when
+    /// nothing is written before the deadline. The written value is not observed by this
+    /// method and is reported by [Watcher::has_changed] afterwards.
+    pub fn block_until_initialized(&mut self, timeout: Duration) -> Result<(), Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            self.shared.tick.try_get().ok_or(Error::Closed)?;
+            if self.shared.header.written.load(Ordering::SeqCst) != 0 {
+                return Ok(());
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::TimedOut);
+            }
+
+            thread::sleep(WAIT_INTERVAL.min(deadline - now));
+        }
+    }
+
+    /// Block current thread until value of T changed or `timeout` elapsed.
+    ///
+    /// Return [PollResult::Changed] with a snapshot of the new value, [PollResult::Closed]
+    /// when [Watched] value is gone or [PollResult::TimedOut]. The tick is checked once more
+    /// when the deadline is reached so a change arriving right before it is not lost.
+    pub fn poll_timeout(&mut self, timeout: Duration) -> PollResult<T> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.try_has_changed() {
+                Ok(true) => return PollResult::Changed(self.read(|val| *val)),
+                Ok(false) => {}
+                Err(e) => {
+                    self.record_error(e);
+                    return PollResult::Closed;
+                }
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return PollResult::TimedOut;
+            }
+
+            thread::sleep(WAIT_INTERVAL.min(deadline - now));
+        }
+    }
+
+    fn wait(&mut self, token: Option<&CancelToken>) -> Result<(), Error> {
+        loop {
+            if self.try_has_changed()? {
+                return Ok(());
+            }
+
+            if token.map(CancelToken::is_cancelled).unwrap_or(false) {
+                return Err(Error::Cancelled);
+            }
+
+            thread::sleep(WAIT_INTERVAL);
+        }
+    }
+
+    fn try_has_changed(&mut self) -> Result<bool, Error> {
+        // tick is reset with generation in the same meta write by a new Watched.
+        let (generation, (tick_new, closed)) = self.shared.header.read_meta(|header| {
+            (
+                header.generation.load(Ordering::SeqCst),
+                self.shared.tick.load(),
+            )
+        });
+        if generation != self.tick_generation {
+            // ticks of previous Watched are not comparable. re-sync to the restarted count.
+            self.tick_generation = generation;
+            self.tick = 0;
+            self.attach_tick = None;
+            self.recent = TickWindow::new();
+        }
+
+        // the last tick advance before close is still reported as change.
+        if tick_new != self.tick {
+            if self.recent.is_backward(tick_new) {
+                self.out_of_order = true;
+                self.record_error(Error::OutOfOrder {
+                    last: self.tick,
+                    found: tick_new,
+                });
+            }
+            self.recent.push(tick_new);
+
+            // the current value at construction is reported as change without overruns.
+            let base = self.attach_tick.take().unwrap_or(self.tick);
+            let distance = Tick::distance(base, tick_new);
+            if distance > 1 {
+                self.overruns += distance as usize - 1;
+                self.first_overrun.get_or_insert(tick_new);
+            }
+            self.tick = tick_new;
+            self.resync = self.shared.header.resync.load(Ordering::SeqCst);
+            return Ok(true);
+        }
+
+        if closed {
+            return Err(Error::Closed);
+        }
+
+        let resync = self.shared.header.resync.load(Ordering::SeqCst);
+        if resync != self.resync {
+            self.resync = resync;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Append the value published since last observe to `out` and return the count of
+    /// appended values.
+    ///
+    /// [Watched] only keep the latest snapshot of `T` so at most one value is appended per
+    /// call. Updates overwritten before this watcher could observe them are not appended
+    /// and counted by [Watcher::overruns] instead.
+    ///
+    /// # panics:
+    ///
+    /// When [Watched] value is gone. See [Watcher::has_changed].
+    pub fn drain_updates_into(&mut self, out: &mut Vec<T>) -> usize {
+        if !self.has_changed() {
+            return 0;
+        }
+
+        self.read(|val| out.push(*val));
+        1
+    }
+
+    /// Total count of updates this watcher missed because they are overwritten by a newer
+    /// value before being observed. Writes before construction of this watcher are not
+    /// counted.
+    ///
+    /// The tick counter is wrapping so more than 127 writes between two observes can not be
+    /// counted precisely.
+    pub fn overruns(&self) -> usize {
+        self.overruns
+    }
+
+    /// The tick value observed when this watcher missed update(s) for the first time.
+    /// `None` when every update has been observed so far.
+    pub fn first_overrun_tick(&self) -> Option<u8> {
+        self.first_overrun
+    }
+
+    /// The last recoverable issue this watcher encountered. It's one of:
+    ///
+    /// - [Error::OutOfOrder] when a backward tick is observed.
+    /// - [Error::Closed] when [Watched] value is gone but [Watcher::read_cached] returned the
+    ///   last copy or [Watcher::poll_timeout] returned [PollResult::Closed].
+    /// - [Error::TimedOut] when a read failed to obtain the lock in time. Like
+    ///   [Watcher::try_read] and [Watcher::read_timeout].
+    /// - [Error::TooContended] when [Watcher::read_bounded] exhausted it's retry budget.
+    ///
+    /// Issues are recorded even when they are also returned to caller so they leave a trail
+    /// after the error is handled. An issue happened while the returned reference is held is
+    /// not recorded.
+    pub fn last_error(&self) -> Option<Ref<'_, Error>> {
+        Ref::filter_map(self.last_error.borrow(), Option::as_ref).ok()
+    }
+
+    fn record_error(&self, e: Error) {
+        if let Ok(mut last) = self.last_error.try_borrow_mut() {
+            *last = Some(e);
+        }
+    }
+
+    /// Return true when this watcher ever observed the tick going backward.
+    ///
+    /// The tick is a wrapping counter so it going from it's max value to 0 is a forward move
+    /// and not reported. A tick is only reported as backward when it's behind the last
+    /// observed tick in wrapping order and it's one of the few most recent observed ticks.
+    /// This happens when multiple writers race on the same value. A [Watched] value
+    /// reconstructed and restart counting is a new generation and not reported.
+    pub fn observed_out_of_order(&self) -> bool {
+        self.out_of_order
+    }
+}
+
+impl<T: Copy + Send + Sync, S: SyncStrategy> Drop for Watcher<'_, T, S> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.ack_slot {
+            self.shared.header.release_ack(slot);
+        }
+        self.shared.header.watchers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Construct a new observer from given [Shmem].
+/// See [Watcher::new_from_mem].
+///
+/// The first one attached to the shared memory records layout of `T` in header. Every
+/// construction, including the first, validates the shared memory in this order:
+///
+/// - [Error::MemoryTooSmall] when [Shmem] is not large enough for header, lock and `T`.
+/// - [Error::InitStalled] when header is being initialized by another one not finishing in
+///   time.
+/// - [Error::TickWidthMismatch] when header records a tick counter of different width.
+/// - [Error::SyncMismatch] when header records a different [SyncStrategy] than `S`.
+/// - [Error::LayoutMismatch] when header records a `T` of different size or alignment.
+/// - [Error::InvalidLayout] when data offset recorded in header overlaps the lock.
+/// - [Error::MemoryTooSmall] when [Shmem] is not large enough for `T` at the recorded data
+///   offset.
+/// - [Error::Misaligned] when the recorded data offset is not aligned to `T`.
+impl<'a, T: Copy + Send + Sync, S: SyncStrategy> TryFrom<&'a mut Shmem> for Watcher<'a, T, S> {
+    type Error = Error;
+
+    fn try_from(mem: &'a mut Shmem) -> Result<Self, Self::Error> {
+        let link = mem
+            .get_flink_path()
+            .map(|path| (path.clone(), mem.get_os_id().to_string()));
+        Self::try_from_memory(mem, link)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug)]
+    struct Foo([u8; 512]);
+
+    #[test]
+    fn works() {
+        let mut mem = shared_memory_create("./test_file", 1024).unwrap();
+
+        let watched = Watched::<Foo>::new_from_mem(&mut mem);
+
+        watched.write(Foo([123; 512]));
+
+        std::thread::spawn(|| {
+            let mut mem = shared_memory_open("./test_file", 1024).unwrap();
+
+            let mut watcher = Watcher::<Foo>::new_from_mem(&mut mem);
+
+            assert!(watcher.has_changed());
+
+            watcher.read(|foo| assert_eq!(foo.0.len(), 512));
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn drain_updates() {
+        let mut mem = shared_memory_create("./test_file4", 1024).unwrap();
+
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+
+        let mut mem = shared_memory_open("./test_file4", 1024).unwrap();
+
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+
+        let mut out = Vec::new();
+        assert_eq!(watcher.drain_updates_into(&mut out), 0);
+
+        watched.write(1);
+        watched.write(2);
+        watched.write(3);
+
+        assert_eq!(watcher.drain_updates_into(&mut out), 1);
+        assert_eq!(watcher.drain_updates_into(&mut out), 0);
+        assert_eq!(out, [3]);
+        assert_eq!(watcher.overruns(), 2);
+    }
+
+    #[test]
+    fn late_attach_overruns() {
+        let mut mem = shared_memory_create("./test_file54", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        for i in 0..20 {
+            watched.write(i);
+        }
+
+        let mut mem2 = shared_memory_open("./test_file54", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        // current value is still reported as change.
+        assert_eq!(*watcher.read_cached(), 19);
+        assert_eq!(watcher.overruns(), 0);
+
+        let mut mem3 = shared_memory_open("./test_file54", 1024).unwrap();
+        let mut watcher2 = Watcher::<u8>::new_from_mem(&mut mem3);
+        let mut out = Vec::new();
+        assert_eq!(watcher2.drain_updates_into(&mut out), 1);
+        assert_eq!(watcher2.overruns(), 0);
+
+        // writes after construction are still counted.
+        watched.write(20);
+        watched.write(21);
+        assert_eq!(watcher2.drain_updates_into(&mut out), 1);
+        assert_eq!(out, [19, 21]);
+        assert_eq!(watcher2.overruns(), 1);
+    }
+
+    #[test]
+    fn first_overrun() {
+        let mut mem = shared_memory_create("./test_file5", 1024).unwrap();
+
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+
+        let mut mem = shared_memory_open("./test_file5", 1024).unwrap();
+
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+
+        watched.write(1);
+        assert!(watcher.has_changed());
+        assert_eq!(watcher.first_overrun_tick(), None);
+
+        watched.write(2);
+        watched.write(3);
+        assert!(watcher.has_changed());
+        let tick = watched.0.tick.try_get();
+        assert_eq!(watcher.first_overrun_tick(), tick);
+
+        watched.write(4);
+        watched.write(5);
+        assert!(watcher.has_changed());
+        assert_eq!(watcher.first_overrun_tick(), tick);
+
+        // late attached watcher never fell behind.
+        let mut mem = shared_memory_open("./test_file5", 1024).unwrap();
+        let mut late = Watcher::<u8>::new_from_mem(&mut mem);
+        assert!(late.has_changed());
+        assert_eq!(late.first_overrun_tick(), None);
+    }
+
+    #[test]
+    fn write_with_version() {
+        let mut mem = shared_memory_create("./test_file6", 1024).unwrap();
+
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+
+        let mut mem = shared_memory_open("./test_file6", 1024).unwrap();
+
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+
+        for _ in 0..300 {
+            watched.write_with_version(|tick| tick);
+            assert!(watcher.has_changed());
+            watcher.read(|tick| assert_eq!(Some(*tick), watched.0.tick.try_get()));
+        }
+    }
+
+    #[test]
+    fn wait_for_change_cancel() {
+        let mut mem = shared_memory_create("./test_file7", 1024).unwrap();
+
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+
+        let token = CancelToken::new();
+
+        let handle = {
+            let token = token.clone();
+            std::thread::spawn(move || {
+                let mut mem = shared_memory_open("./test_file7", 1024).unwrap();
+
+                let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+
+                watcher.wait_for_change_cancellable(&token).unwrap();
+                watcher.read(|val| assert_eq!(*val, 1));
+
+                matches!(
+                    watcher.wait_for_change_cancellable(&token),
+                    Err(Error::Cancelled)
+                )
+            })
+        };
+
+        std::thread::sleep(Duration::from_millis(50));
+        watched.write(1);
+        std::thread::sleep(Duration::from_millis(50));
+        token.cancel();
+
+        assert!(handle.join().unwrap());
+    }
+
+    #[test]
+    fn out_of_order() {
+        let mut mem = shared_memory_create("./test_file8", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+
+        let mut mem = shared_memory_open("./test_file8", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+
+        for i in 0..200 {
+            watched.write(i);
+            assert!(watcher.has_changed());
+        }
+        assert!(!watcher.observed_out_of_order());
+        assert!(watcher.last_error().is_none());
+
+        // a restarted writer counting from 0 is re-synced and not out of order.
+        let mut mem = shared_memory_open("./test_file8", 1024).unwrap();
+        let watched2 = Watched::<u8>::new_from_mem(&mut mem);
+        for i in 0..199 {
+            watched2.write(i);
+        }
+        assert!(watcher.has_changed());
+        watched2.write(199);
+        assert!(watcher.has_changed());
+        assert!(!watcher.observed_out_of_order());
+
+        // tick going back right behind the last observed one without a restart.
+        watched2.0.tick.store(142);
+        assert!(watcher.has_changed());
+        assert!(watcher.observed_out_of_order());
+        assert!(matches!(
+            watcher.last_error().as_deref(),
+            Some(Error::OutOfOrder {
+                last: 144,
+                found: 142
+            })
+        ));
+
+        drop(watched2);
+        drop(watched);
+    }
+
+    #[test]
+    fn write_with_callback() {
+        let mut mem = shared_memory_create("./test_file11", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+
+        watched.write_with_callback(1, |tick| {
+            assert_eq!(watched.0.tick.try_get(), Some(tick));
+            // lock is released.
+            watched.write(2);
+        });
+
+        watched.0.read(|val| assert_eq!(*val, 2));
+    }
+
+    #[test]
+    fn sequence() {
+        let mut mem = shared_memory_create("./test_file9", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+
+        let mut mem = shared_memory_open("./test_file9", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+
+        assert_eq!(watcher.sequence(), 0);
+
+        watched.set_sequence(u64::MAX);
+        assert_eq!(watcher.sequence(), u64::MAX);
+        assert!(!watcher.has_changed());
+
+        watched.write(1);
+        assert!(watcher.has_changed());
+        assert_eq!(watcher.sequence(), u64::MAX);
+    }
+
+    #[test]
+    fn watcher_first() {
+        let mut mem = shared_memory_create("./test_file10", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+        assert!(!watcher.has_changed());
+
+        let mut mem = shared_memory_open("./test_file10", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        assert!(!watcher.has_changed());
+
+        watched.write(1);
+        assert!(watcher.has_changed());
+        watcher.read(|val| assert_eq!(*val, 1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn drop_watched() {
+        let mut mem = shared_memory_create("./test_file2", 1024).unwrap();
+
+        let watched = Watched::<Foo>::new_from_mem(&mut mem);
+
+        drop(watched);
+
+        let mut mem = shared_memory_open("./test_file2", 1024).unwrap();
+
+        let mut watcher = Watcher::<Foo>::new_from_mem(&mut mem);
+
+        watcher.has_changed();
+    }
+
+    #[test]
+    fn try_from() {
+        {
+            let mut mem = shared_memory_create("./test_file12", 20).unwrap();
+            let res = Watched::<Foo>::try_from(&mut mem);
+            assert!(matches!(res, Err(Error::MemoryTooSmall { actual: 20, .. })));
+        }
+
+        let mut mem = shared_memory_create("./test_file12", 1024).unwrap();
+        let _watched: Watched<Foo> = (&mut mem).try_into().unwrap();
+        let mut mem = shared_memory_open("./test_file12", 1024).unwrap();
+        let _watcher: Watcher<Foo> = (&mut mem).try_into().unwrap();
+    }
+
+    #[test]
+    fn data_align() {
+        let mut mem = shared_memory_create("./test_file13", 1024).unwrap();
+        let watched = Watched::<u8>::builder()
+            .data_align(256)
+            .build(&mut mem)
+            .unwrap();
+        watched.write(1);
+        watched
+            .0
+            .read(|val| assert_eq!(val as *const u8 as usize % 256, 0));
+
+        let mut mem = shared_memory_open("./test_file13", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+        assert!(watcher.has_changed());
+        watcher.read(|val| {
+            assert_eq!(val as *const u8 as usize % 256, 0);
+            assert_eq!(*val, 1);
+        });
+
+        let mut mem = shared_memory_create("./test_file13", 1024).unwrap();
+        assert!(matches!(
+            Watched::<u8>::builder().data_align(2048).build(&mut mem),
+            Err(Error::MemoryTooSmall { .. })
+        ));
+
+        // reserved by watcher with default alignment.
+        let _watcher = Watcher::<u8>::new_from_mem(&mut mem);
+        let mut mem = shared_memory_open("./test_file13", 1024).unwrap();
+        let res = Watched::<u8>::builder().data_align(256).build(&mut mem);
+        assert!(matches!(res, Err(Error::Misaligned { align: 256 })));
+    }
+
+    #[test]
+    fn reinit_lock() {
+        let mut mem = shared_memory_create("./test_file14", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        watched.write(1);
+
+        let mut mem = shared_memory_open("./test_file14", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+
+        // lock is left held by a dead writer.
+        mem::forget(watched.0.lock.lock().unwrap());
+
+        // SAFETY:
+        // no one else is holding or waiting for the lock.
+        unsafe { watcher.reinit_lock() };
+
+        watcher.read(|val| assert_eq!(*val, 1));
+        watched.write(2);
+        watcher.read(|val| assert_eq!(*val, 2));
+    }
+
+    #[test]
+    fn read_cached() {
+        let mut mem = shared_memory_create("./test_file15", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        watched.write(1);
+
+        let mut mem = shared_memory_open("./test_file15", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+        assert_eq!(*watcher.read_cached(), 1);
+
+        // cached value is served without read lock.
+        let guard = watched.0.lock.lock().unwrap();
+        assert_eq!(*watcher.read_cached(), 1);
+        drop(guard);
+
+        watched.write(2);
+        assert_eq!(*watcher.read_cached(), 2);
+        assert!(!watcher.has_changed());
+
+        assert!(watcher.last_error().is_none());
+        drop(watched);
+        assert_eq!(*watcher.read_cached(), 2);
+        assert!(matches!(
+            watcher.last_error().as_deref(),
+            Some(Error::Closed)
+        ));
+    }
+
+    #[test]
+    fn last_error_reads() {
+        let mut mem = shared_memory_create("./test_file55", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open("./test_file55", 1024).unwrap();
+        let watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+
+        let guard = watched.0.lock.lock().unwrap();
+        assert!(matches!(
+            watcher.read_timeout(Duration::from_millis(10), |_| ()),
+            Err(Error::TimedOut)
+        ));
+        assert!(matches!(
+            watcher.last_error().as_deref(),
+            Some(Error::TimedOut)
+        ));
+        assert!(matches!(
+            watcher.read_bounded(2, |_| ()),
+            Err(Error::TooContended)
+        ));
+        assert!(matches!(
+            watcher.last_error().as_deref(),
+            Some(Error::TooContended)
+        ));
+        drop(guard);
+
+        // successful read keeps the trail.
+        watcher.read_bounded(0, |_| ()).unwrap();
+        assert!(matches!(
+            watcher.last_error().as_deref(),
+            Some(Error::TooContended)
+        ));
+    }
+
+    #[test]
+    fn generation() {
+        let mut mem = shared_memory_create("./test_file16", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        watched.write(1);
+
+        let mut mem = shared_memory_open("./test_file16", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+        assert_eq!(watcher.generation(), 1);
+        assert!(!watcher.generation_changed());
+        assert!(watcher.has_changed());
+
+        let mut mem = shared_memory_open("./test_file16", 1024).unwrap();
+        let watched2 = Watched::<u8>::new_from_mem(&mut mem);
+        assert!(watcher.generation_changed());
+        assert_eq!(watcher.generation(), 2);
+        assert!(!watcher.generation_changed());
+
+        watched2.write(2);
+        assert!(watcher.has_changed());
+        assert!(!watcher.observed_out_of_order());
+        watcher.read(|val| assert_eq!(*val, 2));
+
+        // restart is not a change or overrun without generation_changed.
+        for i in 0..10 {
+            watched2.write(i);
+        }
+        assert!(watcher.has_changed());
+        let overruns = watcher.overruns();
+        let first_overrun = watcher.first_overrun_tick();
+        drop(watched2);
+        let mut mem = shared_memory_open("./test_file16", 1024).unwrap();
+        let watched3 = Watched::<u8>::new_from_mem(&mut mem);
+        assert!(!watcher.has_changed());
+        assert_eq!(watcher.overruns(), overruns);
+        assert_eq!(watcher.first_overrun_tick(), first_overrun);
+
+        watched3.write(3);
+        assert!(watcher.has_changed());
+        assert!(!watcher.observed_out_of_order());
+        assert_eq!(watcher.overruns(), overruns);
+        watcher.read(|val| assert_eq!(*val, 3));
+        assert!(watcher.generation_changed());
+
+        drop(watched3);
+        drop(watched);
+    }
+
+    #[test]
+    fn read_ptr() {
+        let mut mem = shared_memory_create("./test_file17", 1024).unwrap();
+        let watched = Watched::<Foo>::new_from_mem(&mut mem);
+        watched.write(Foo([7; 512]));
+
+        let mut mem = shared_memory_open("./test_file17", 1024).unwrap();
+        let watcher = Watcher::<Foo>::new_from_mem(&mut mem);
+
+        // SAFETY:
+        // pointer is only read in the closure.
+        let sum =
+            unsafe { watcher.read_ptr(|ptr| (*ptr).0.iter().map(|b| *b as usize).sum::<usize>()) };
+        assert_eq!(sum, 7 * 512);
+    }
+
+    #[test]
+    fn writer_gone() {
+        let path = "./test_file18";
+
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open(path, 1024).unwrap();
+        let watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        assert!(!watcher.writer_gone());
+        drop(watched);
+        assert!(watcher.writer_gone());
+
+        // file link is removed.
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let _watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open(path, 1024).unwrap();
+        let watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        assert!(!watcher.writer_gone());
+        fs::remove_file(path).unwrap();
+        assert!(watcher.writer_gone());
+
+        // file link is replaced.
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let _watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open(path, 1024).unwrap();
+        let watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        let mut mem3 = shared_memory_create(path, 1024).unwrap();
+        let _watched2 = Watched::<u8>::new_from_mem(&mut mem3);
+        assert!(watcher.writer_gone());
+    }
+
+    #[test]
+    fn into_watcher() {
+        let mut mem = shared_memory_create("./test_file19", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        watched.write(1);
+
+        let mut mem2 = shared_memory_open("./test_file19", 1024).unwrap();
+        let mut other = Watcher::<u8>::new_from_mem(&mut mem2);
+        assert!(other.has_changed());
+
+        let mut watcher = watched.into_watcher();
+        assert!(!watcher.has_changed());
+        assert!(!watcher.writer_gone());
+        assert!(!other.writer_gone());
+        watcher.read(|val| assert_eq!(*val, 1));
+    }
+
+    #[test]
+    fn sync_strategy() {
+        let mut mem = shared_memory_create("./test_file20", 1024).unwrap();
+        let watched = Watched::<u8, MutexSync>::new_from_mem(&mut mem);
+        watched.write(1);
+
+        let mut mem = shared_memory_open("./test_file20", 1024).unwrap();
+        let mut watcher = Watcher::<u8, MutexSync>::new_from_mem(&mut mem);
+        assert!(watcher.has_changed());
+        watcher.read(|val| assert_eq!(*val, 1));
+
+        let mut mem = shared_memory_open("./test_file20", 1024).unwrap();
+        assert!(matches!(
+            Watcher::<u8>::try_from(&mut mem),
+            Err(Error::SyncMismatch {
+                expected: RwLockSync::ID,
+                found: MutexSync::ID
+            })
+        ));
+    }
+
+    #[test]
+    fn poll_timeout() {
+        let mut mem = shared_memory_create("./test_file21", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open("./test_file21", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+
+        let timeout = Duration::from_millis(20);
+        assert_eq!(watcher.poll_timeout(timeout), PollResult::TimedOut);
+
+        // change before deadline is delivered even with zero timeout.
+        watched.write(3);
+        assert_eq!(watcher.poll_timeout(Duration::ZERO), PollResult::Changed(3));
+        assert_eq!(watcher.poll_timeout(Duration::ZERO), PollResult::TimedOut);
+
+        drop(watched);
+        assert_eq!(watcher.poll_timeout(timeout), PollResult::Closed);
+    }
+
+    #[test]
+    fn wait_for_tick() {
+        let mut mem = shared_memory_create("./test_file22", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+
+        let handle = std::thread::spawn(|| {
+            let mut mem = shared_memory_open("./test_file22", 1024).unwrap();
+            let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+            watcher.wait_for_tick(10).unwrap();
+            let mut seen = 0;
+            watcher.read(|val| seen = *val);
+            // already passed.
+            watcher.wait_for_tick(4).unwrap();
+            seen
+        });
+
+        for i in 1..=5 {
+            std::thread::sleep(Duration::from_millis(5));
+            watched.write(i);
+        }
+        assert_eq!(handle.join().unwrap(), 5);
+
+        let mut mem2 = shared_memory_open("./test_file22", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        // tick wraps to 4 and target 2 is passed.
+        for i in 0..125 {
+            watched.write(i);
+        }
+        watcher.wait_for_tick(2).unwrap();
+        assert_eq!(watched.0.tick.try_get(), Some(4));
+
+        watched.write(0);
+        drop(watched);
+        // reached before close.
+        watcher.wait_for_tick(6).unwrap();
+        watcher.wait_for_tick(6).unwrap();
+        assert!(matches!(watcher.wait_for_tick(100), Err(Error::Closed)));
+    }
+
+    #[test]
+    fn layout_mismatch() {
+        let mut mem = shared_memory_create("./test_file23", 1024).unwrap();
+        let _watched = Watched::<u32>::new_from_mem(&mut mem);
+
+        let mut mem2 = shared_memory_open("./test_file23", 1024).unwrap();
+        assert!(matches!(
+            Watcher::<u64>::try_from(&mut mem2),
+            Err(Error::LayoutMismatch {
+                writer_size: 4,
+                reader_size: 8,
+                writer_align: 4,
+                reader_align: 8,
+            })
+        ));
+        assert!(Watcher::<[u8; 4]>::try_from(&mut mem2).is_err());
+        assert!(Watcher::<u32>::try_from(&mut mem2).is_ok());
+    }
+
+    #[test]
+    fn read_bounded() {
+        let mut mem = shared_memory_create("./test_file24", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        watched.write(1);
+
+        let mut mem2 = shared_memory_open("./test_file24", 1024).unwrap();
+        let watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        assert_eq!(watcher.read_bounded(0, |val| *val).unwrap(), 1);
+
+        // writer holding the lock exhaust the budget.
+        let guard = watched.0.lock.lock().unwrap();
+        assert!(matches!(
+            watcher.read_bounded(100, |val| *val),
+            Err(Error::TooContended)
+        ));
+        drop(guard);
+        assert_eq!(watcher.read_bounded(0, |val| *val).unwrap(), 1);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn total_writes() {
+        let mut mem = shared_memory_create("./test_file45", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open("./test_file45", 1024).unwrap();
+        let watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        assert_eq!(watcher.observed_total(), 0);
+
+        for i in 0..300u32 {
+            watched.write(i as u8);
+        }
+        watched.pause();
+        watched.write(1);
+        watched.resume();
+        assert_eq!(watched.total_writes(), 301);
+        assert_eq!(watcher.observed_total(), 301);
+    }
+
+    #[test]
+    fn value_or_default() {
+        let mut mem = shared_memory_create("./test_file46", 1024).unwrap();
+        let mut mem2 = shared_memory_open("./test_file46", 1024).unwrap();
+        let watcher = Watcher::<u64>::new_from_mem(&mut mem2);
+        assert_eq!(watcher.value_or_default(), 0);
+
+        let watched = Watched::<u64>::new_from_mem(&mut mem);
+        assert_eq!(watcher.value_or_default(), 0);
+        watched.write(7);
+        assert_eq!(watcher.value_or_default(), 7);
+    }
+
+    #[test]
+    fn write_and_wait_ack() {
+        let path = "./test_file47";
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let responder = std::thread::spawn(move || {
+            let mut mem = shared_memory_open(path, 1024).unwrap();
+            let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+            tx.send(()).unwrap();
+            watcher.wait_for_change().unwrap();
+            let val = watcher.read(|val| *val);
+            watcher.ack();
+            val
+        });
+        rx.recv().unwrap();
+
+        watched
+            .write_and_wait_ack(1, Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(responder.join().unwrap(), 1);
+
+        // responder reading the value and never acknowledging it.
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+        let responder = std::thread::spawn(move || {
+            let mut mem = shared_memory_open(path, 1024).unwrap();
+            let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+            tx.send(()).unwrap();
+            watcher.wait_for_change().unwrap();
+            let val = watcher.read(|val| *val);
+            done_rx.recv().unwrap();
+            val
+        });
+        rx.recv().unwrap();
+
+        assert!(matches!(
+            watched.write_and_wait_ack(2, Duration::from_millis(50)),
+            Err(Error::TimedOut)
+        ));
+        done_tx.send(()).unwrap();
+        assert_eq!(responder.join().unwrap(), 2);
+
+        let mut mem2 = shared_memory_open(path, 1024).unwrap();
+        let _watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        assert!(matches!(
+            watched.write_and_wait_ack(3, Duration::from_millis(20)),
+            Err(Error::TimedOut)
+        ));
+    }
+
+    #[test]
+    fn is_same_segment() {
+        let mut mem = shared_memory_create("./test_file48", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open("./test_file48", 1024).unwrap();
+        let watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        let mut mem3 = shared_memory_open("./test_file48", 1024).unwrap();
+        let watcher2 = Watcher::<u8>::new_from_mem(&mut mem3);
+        assert!(watcher.is_same_segment(&watcher2));
+        assert!(watcher.is_same_segment(&watcher));
+
+        let mut mem4 = shared_memory_create("./test_file49", 1024).unwrap();
+        let other = Watched::<u8>::new_from_mem(&mut mem4);
+        assert!(!watched.is_same_segment(&other));
+        let mut mem5 = shared_memory_open("./test_file49", 1024).unwrap();
+        let watcher3 = Watcher::<u8>::new_from_mem(&mut mem5);
+        assert!(!watcher.is_same_segment(&watcher3));
+
+        #[cfg(unix)]
+        {
+            // anonymous memory is compared by address.
+            let anon = AnonMem::new(1024).unwrap();
+            let watcher = Watcher::<u8>::try_from_memory(&anon, None).unwrap();
+            let watcher2 = Watcher::<u8>::try_from_memory(&anon, None).unwrap();
+            assert!(watcher.is_same_segment(&watcher2));
+            let anon2 = AnonMem::new(1024).unwrap();
+            let watcher3 = Watcher::<u8>::try_from_memory(&anon2, None).unwrap();
+            assert!(!watcher.is_same_segment(&watcher3));
+        }
+    }
+
+    #[test]
+    fn write_guarded() {
+        let mut mem = shared_memory_create("./test_file44", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open("./test_file44", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+
+        let even = |val: &u8| val.is_multiple_of(2);
+        watched.write_guarded(2, even).unwrap();
+        assert!(watcher.has_changed());
+        let tick = watched.0.tick.load().0;
+
+        assert!(matches!(
+            watched.write_guarded(3, even),
+            Err(Error::InvariantViolated)
+        ));
+        assert_eq!(watched.0.tick.load().0, tick);
+        assert!(!watcher.has_changed());
+        assert_eq!(watcher.read(|val| *val), 2);
+    }
+
+    #[test]
+    fn default_timeout() {
+        let mut mem = shared_memory_create("./test_file41", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open("./test_file41", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+
+        // not bounded by default.
+        watched.try_write(1).unwrap();
+        assert_eq!(watcher.try_read(|val| *val).unwrap(), 1);
+
+        let guard = watched.0.lock.lock().unwrap();
+        watcher.set_default_timeout(Duration::from_millis(10));
+        assert!(matches!(watcher.try_read(|val| *val), Err(Error::TimedOut)));
+        // read without error panics.
+        let read = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| watcher.read(|_| ())));
+        assert!(read.is_err());
+        watcher.set_default_timeout(Duration::from_secs(60));
+        let now = Instant::now();
+        assert!(matches!(
+            watcher.read_timeout(Duration::from_millis(10), |val| *val),
+            Err(Error::TimedOut)
+        ));
+        assert!(now.elapsed() < Duration::from_secs(60));
+        drop(guard);
+        assert_eq!(watcher.try_read(|val| *val).unwrap(), 1);
+
+        let guard = watcher.shared.lock.rlock().unwrap();
+        watched.set_default_timeout(Duration::from_millis(10));
+        assert!(matches!(watched.try_write(2), Err(Error::TimedOut)));
+        let write = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| watched.write(2)));
+        assert!(write.is_err());
+        watched.set_default_timeout(Duration::from_secs(60));
+        let now = Instant::now();
+        assert!(matches!(
+            watched.write_timeout(2, Duration::from_millis(10)),
+            Err(Error::TimedOut)
+        ));
+        assert!(now.elapsed() < Duration::from_secs(60));
+        drop(guard);
+        watched.try_write(2).unwrap();
+        assert!(watcher.has_changed());
+        assert_eq!(watcher.read(|val| *val), 2);
+    }
+
+    #[test]
+    fn sub_second_timeout() {
+        let mut mem = shared_memory_create("./test_file52", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open("./test_file52", 1024).unwrap();
+        let watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        watched.write(1);
+
+        // a timeout close to a full second is waited out in whole.
+        let guard = watched.0.lock.lock().unwrap();
+        let now = Instant::now();
+        assert!(matches!(
+            watcher.read_timeout(Duration::from_millis(999), |val| *val),
+            Err(Error::TimedOut)
+        ));
+        assert!(now.elapsed() >= Duration::from_millis(999));
+        drop(guard);
+
+        // and lock released in the middle of it is obtained.
+        let (tx, rx) = std::sync::mpsc::channel();
+        let handle = std::thread::spawn(move || {
+            let mut mem = shared_memory_open("./test_file52", 1024).unwrap();
+            let watcher = Watcher::<u8>::new_from_mem(&mut mem);
+            let _guard = watcher.shared.lock.rlock().unwrap();
+            tx.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(300));
+        });
+        rx.recv().unwrap();
+        watched
+            .write_timeout(2, Duration::from_millis(1999))
+            .unwrap();
+        handle.join().unwrap();
+        assert_eq!(watcher.read(|val| *val), 2);
+    }
+
+    #[test]
+    fn watcher_count() {
+        use std::sync::mpsc;
+
+        let mut mem = shared_memory_create("./test_file25", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        assert_eq!(watched.watcher_count(), 0);
+
+        let (tx, rx) = mpsc::channel();
+        watched.on_watcher_count_change(move |count| tx.send(count).unwrap());
+
+        let mut mem2 = shared_memory_open("./test_file25", 1024).unwrap();
+        let watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        assert_eq!(watched.watcher_count(), 1);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(1));
+
+        drop(watcher);
+        assert_eq!(watched.watcher_count(), 0);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(0));
+
+        // monitor thread is stopped and it's sender is dropped.
+        drop(watched);
+        assert_eq!(rx.recv(), Err(mpsc::RecvError));
+    }
+
+    #[test]
+    fn block_until_initialized() {
+        let path = "./test_file26";
+        let timeout = Duration::from_millis(20);
+
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+        assert!(matches!(
+            watcher.block_until_initialized(timeout),
+            Err(Error::TimedOut)
+        ));
+
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            let mut mem = shared_memory_open(path, 1024).unwrap();
+            let watched = Watched::<u8>::new_from_mem(&mut mem);
+            std::thread::sleep(Duration::from_millis(20));
+            watched.write(1);
+            // leave the value open.
+            watched.into_watcher();
+        });
+        watcher
+            .block_until_initialized(Duration::from_secs(5))
+            .unwrap();
+        assert!(watcher.has_changed());
+        handle.join().unwrap();
+
+        // constructed, written and dropped before watcher looked.
+        let mut mem2 = shared_memory_open(path, 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem2);
+        watched.write(2);
+        drop(watched);
+        assert!(matches!(
+            watcher.block_until_initialized(timeout),
+            Err(Error::Closed)
+        ));
+    }
+
+    #[test]
+    fn observe() {
+        let path = "./test_file27";
+        assert!(super::observe::<u8>(path).is_err());
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            let mut mem = shared_memory_create(path, 1024).unwrap();
+            let watched = Watched::<u8>::new_from_mem(&mut mem);
+            watched.write(5);
+            rx.recv().unwrap();
+        });
+
+        let mut watcher = super::observe::<u8>(path).unwrap();
+        watcher.wait_for_change().unwrap();
+        watcher.read(|val| assert_eq!(*val, 5));
+        assert!(matches!(
+            super::observe::<u32>(path),
+            Err(Error::LayoutMismatch { .. })
+        ));
+
+        tx.send(()).unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn pause() {
+        let mut mem = shared_memory_create("./test_file28", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open("./test_file28", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+
+        watched.pause();
+        watched.write(1);
+        watched.write(2);
+        watched.write_with_callback(3, |tick| assert_eq!(tick, 2));
+        assert!(!watcher.has_changed());
+        watched.resume();
+        assert!(watcher.has_changed());
+        watcher.read(|val| assert_eq!(*val, 3));
+        assert!(!watcher.has_changed());
+        assert_eq!(watcher.overruns(), 0);
+
+        // resume without write is not notified.
+        watched.pause();
+        watched.resume();
+        assert!(!watcher.has_changed());
+
+        // resume publishes under the write lock.
+        watched.pause();
+        watched.write(4);
+        let guard = watcher.shared.lock.rlock().unwrap();
+        watched.set_default_timeout(Duration::from_millis(10));
+        let resume = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| watched.resume()));
+        assert!(resume.is_err());
+        drop(guard);
+        assert!(!watcher.has_changed());
+        watched.resume();
+        assert!(watcher.has_changed());
+        watcher.read(|val| assert_eq!(*val, 4));
+
+        // pending write is published on conversion.
+        watched.pause();
+        watched.write(5);
+        let mut watcher2 = watched.into_watcher();
+        assert!(!watcher2.has_changed());
+        assert!(watcher.has_changed());
+        watcher.read(|val| assert_eq!(*val, 5));
+
+        // and on drop as the final value.
+        let mut mem3 = shared_memory_open("./test_file28", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem3);
+        watched.pause();
+        watched.write(6);
+        drop(watched);
+        assert!(watcher.has_changed());
+        watcher.read(|val| assert_eq!(*val, 6));
+        assert!(watcher.try_has_changed().is_err());
+    }
+
+    #[test]
+    fn panic_in_read() {
+        let mut mem = shared_memory_create("./test_file29", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        watched.write(1);
+
+        let handle = std::thread::spawn(|| {
+            let mut mem = shared_memory_open("./test_file29", 1024).unwrap();
+            let watcher = Watcher::<u8>::new_from_mem(&mut mem);
+            watcher.read(|_| panic!("reader panic"));
+        });
+        assert!(handle.join().is_err());
+
+        // lock is released by unwinding reader.
+        watched.write(2);
+        let handle = std::thread::spawn(|| {
+            let mut mem = shared_memory_open("./test_file29", 1024).unwrap();
+            let watcher = Watcher::<u8>::new_from_mem(&mut mem);
+            watcher.read(|val| *val)
+        });
+        assert_eq!(handle.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn tick_width_mismatch() {
+        let mut mem = shared_memory_create("./test_file30", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+
+        // a writer with 4 bytes tick.
+        watched.0.header.tick_width.store(4, Ordering::SeqCst);
+
+        let mut mem2 = shared_memory_open("./test_file30", 1024).unwrap();
+        assert!(matches!(
+            Watcher::<u8>::try_from(&mut mem2),
+            Err(Error::TickWidthMismatch {
+                expected: 1,
+                found: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn force_resync() {
+        let mut mem = shared_memory_create("./test_file31", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        watched.write(1);
+
+        let mut mem2 = shared_memory_open("./test_file31", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        let mut mem3 = shared_memory_open("./test_file31", 1024).unwrap();
+        let mut watcher2 = Watcher::<u8>::new_from_mem(&mut mem3);
+        assert!(watcher.has_changed());
+        assert!(!watcher.has_changed());
+
+        watched.force_resync();
+        assert!(watcher.has_changed());
+        assert!(!watcher.has_changed());
+        assert!(!watcher.generation_changed());
+        assert_eq!(watcher.overruns(), 0);
+
+        // write and resync together are one change.
+        assert!(watcher2.has_changed());
+        assert!(!watcher2.has_changed());
+    }
+
+    #[test]
+    fn final_value() {
+        let mut mem = shared_memory_create("./test_file32", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open("./test_file32", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+
+        watched.write(1);
+        watched.write(9);
+        drop(watched);
+
+        assert_eq!(watcher.poll_timeout(Duration::ZERO), PollResult::Changed(9));
+        assert_eq!(watcher.poll_timeout(Duration::ZERO), PollResult::Closed);
+        assert!(matches!(watcher.wait_for_change(), Err(Error::Closed)));
+    }
+
+    #[test]
+    fn prefault() {
+        let mut mem = shared_memory_create("./test_file33", 64 * 1024).unwrap();
+        let watched = Watched::<Foo>::builder()
+            .prefault(true)
+            .build(&mut mem)
+            .unwrap();
+        watched.write(Foo([3; 512]));
+        watched.prefault();
+
+        let mut mem2 = shared_memory_open("./test_file33", 64 * 1024).unwrap();
+        let mut watcher = Watcher::<Foo>::new_from_mem(&mut mem2);
+        assert!(watcher.has_changed());
+        watcher.read(|foo| assert!(foo.0.iter().all(|b| *b == 3)));
+
+        watched.write(Foo([4; 512]));
+        assert!(watcher.has_changed());
+        watcher.read(|foo| assert!(foo.0.iter().all(|b| *b == 4)));
+    }
+
+    #[test]
+    fn read_versioned() {
+        let mut mem = shared_memory_create("./test_file34", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open("./test_file34", 1024).unwrap();
+        let watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+
+        let handle = std::thread::spawn(|| {
+            let mut mem = shared_memory_open("./test_file34", 1024).unwrap();
+            let watcher = Watcher::<u8>::new_from_mem(&mut mem);
+            loop {
+                let (val, generation, tick) = watcher.read_versioned();
+                assert_eq!(generation, 1);
+                // value written with it's own tick.
+                assert_eq!(val, tick);
+                if val == 200 {
+                    break;
+                }
+            }
+        });
+
+        for i in 1..=100 {
+            watched.write_with_version(|tick| {
+                assert_eq!(tick, i * 2);
+                tick
+            });
+        }
+        handle.join().unwrap();
+
+        drop(watched);
+        let mut mem3 = shared_memory_open("./test_file34", 1024).unwrap();
+        let watched2 = Watched::<u8>::new_from_mem(&mut mem3);
+        // value left by the previous writer.
+        assert_eq!(watcher.read_versioned(), (200, 1, 0));
+        watched2.write(7);
+        assert_eq!(watcher.read_versioned(), (7, 2, 2));
+    }
+
+    #[test]
+    fn self_check() {
+        let mut mem = shared_memory_create("./test_file35", 1024).unwrap();
+        let watched = Watched::<u64>::new_from_mem(&mut mem);
+        watched.self_check().unwrap();
+
+        let data_off = watched.0.header.data_off.load(Ordering::SeqCst);
+        watched.0.header.data_off.store(8, Ordering::SeqCst);
+        assert!(matches!(
+            watched.self_check(),
+            Err(Error::InvalidLayout { .. })
+        ));
+        watched
+            .0
+            .header
+            .data_off
+            .store(data_off + 4, Ordering::SeqCst);
+        assert!(matches!(
+            watched.self_check(),
+            Err(Error::Misaligned { align: 8 })
+        ));
+        watched
+            .0
+            .header
+            .data_off
+            .store(data_off + 8, Ordering::SeqCst);
+        assert!(matches!(
+            watched.self_check(),
+            Err(Error::InvalidLayout { .. })
+        ));
+        watched.0.header.data_off.store(data_off, Ordering::SeqCst);
+        watched.self_check().unwrap();
+    }
+
+    #[test]
+    fn snapshot() {
+        let mut mem = shared_memory_create("./test_file36", 1024).unwrap();
+        let watched = Watched::<[u32; 4]>::new_from_mem(&mut mem);
+        watched.write([1, 2, 3, 4]);
+
+        let mut mem2 = shared_memory_open("./test_file36", 1024).unwrap();
+        let mut watcher = Watcher::<[u32; 4]>::new_from_mem(&mut mem2);
+        assert!(watcher.has_changed());
+        let snapshot = watcher.save_snapshot();
+        assert_eq!(snapshot.len(), 16);
+
+        watched.write([0; 4]);
+        assert!(watcher.has_changed());
+        watched.load_snapshot(&snapshot).unwrap();
+        assert!(watcher.has_changed());
+        assert_eq!(watcher.overruns(), 0);
+        watcher.read(|val| assert_eq!(*val, [1, 2, 3, 4]));
+
+        assert!(matches!(
+            watched.load_snapshot(&snapshot[..15]),
+            Err(Error::SnapshotSize {
+                expected: 16,
+                found: 15
+            })
+        ));
+        assert!(!watcher.has_changed());
+    }
+
+    #[test]
+    fn value_generation() {
+        let mut mem = shared_memory_create("./test_file37", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        watched.write(1);
+
+        let mut mem2 = shared_memory_open("./test_file37", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        assert_eq!(watcher.value_generation(), 0);
+        watcher.read(|_| ());
+        assert_eq!(watcher.value_generation(), 1);
+        let cached = *watcher.read_cached();
+
+        // restarted writer.
+        drop(watched);
+        let mut mem3 = shared_memory_open("./test_file37", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem3);
+        assert_eq!(watcher.generation(), 2);
+        assert_eq!(watcher.value_generation(), 1);
+        assert_eq!(cached, 1);
+
+        // value left by the previous writer is read before the first write of new one.
+        watcher.read(|val| assert_eq!(*val, 1));
+        assert_eq!(watcher.value_generation(), 1);
+
+        watched.write(2);
+        assert_eq!(watcher.read_bounded(0, |val| *val).unwrap(), 2);
+        assert_eq!(watcher.value_generation(), 2);
+    }
+
+    #[test]
+    fn poll_change() {
+        use std::{
+            sync::{atomic::AtomicUsize, Arc},
+            task::{Wake, Waker},
+        };
+
+        struct CountWaker(AtomicUsize);
+
+        impl Wake for CountWaker {
+            fn wake(self: Arc<Self>) {
+                self.wake_by_ref();
+            }
+
+            fn wake_by_ref(self: &Arc<Self>) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let count = Arc::new(CountWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut mem = shared_memory_create("./test_file38", 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open("./test_file38", 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+
+        assert!(watcher.poll_change(&mut cx).is_pending());
+
+        let woken = count.0.load(Ordering::SeqCst);
+        // woken right away for polling again.
+        #[cfg(not(feature = "poll-timer"))]
+        assert_eq!(woken, 1);
+
+        watched.write(1);
+        // publish in current process wakes registered waker right away.
+        #[cfg(feature = "poll-timer")]
+        assert!(count.0.load(Ordering::SeqCst) > woken);
+        assert!(matches!(watcher.poll_change(&mut cx), Poll::Ready(Ok(()))));
+
+        drop(watched);
+        assert!(matches!(
+            watcher.poll_change(&mut cx),
+            Poll::Ready(Err(Error::Closed))
+        ));
+    }
+
+    #[cfg(feature = "poll-timer")]
+    #[test]
+    fn poll_change_not_busy() {
+        use std::{
+            sync::Arc,
+            task::{Wake, Waker},
+        };
+
+        struct ThreadWaker(thread::Thread);
+
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let path = "./test_file51";
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let handle = thread::spawn(move || {
+            let mut mem = shared_memory_open(path, 1024).unwrap();
+            let mut watcher = Watcher::<u8>::new_from_mem(&mut mem);
+            let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+            let mut cx = Context::from_waker(&waker);
+            tx.send(()).unwrap();
+
+            // executor parking until woken.
+            let mut polls = 0;
+            while watcher.poll_change(&mut cx).is_pending() {
+                polls += 1;
+                thread::park();
+            }
+            polls
+        });
+        rx.recv().unwrap();
+
+        thread::sleep(Duration::from_millis(100));
+        watched.write(1);
+        let polls = handle.join().unwrap();
+        // woken about every millisecond instead of busy polling.
+        assert!(polls > 0 && polls < 1000, "polled {} times", polls);
+    }
+
+    #[test]
+    fn wait_for_all_acks() {
+        let path = "./test_file39";
+        let timeout = Duration::from_millis(20);
+
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        let mut tick = 0;
+        watched.write_with_callback(1, |t| tick = t);
+        // no watcher registered.
+        watched.wait_for_all_acks(tick, timeout).unwrap();
+
+        let mut mem2 = shared_memory_open(path, 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+
+        // a watcher never acknowledging and detached later.
+        let (tx, rx) = std::sync::mpsc::channel();
+        let handle = std::thread::spawn(move || {
+            let mut mem = shared_memory_open(path, 1024).unwrap();
+            let watcher = Watcher::<u8>::new_from_mem(&mut mem);
+            tx.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(100));
+            drop(watcher);
+        });
+        rx.recv().unwrap();
+
+        watched.write_with_callback(2, |t| tick = t);
+        assert!(matches!(
+            watched.wait_for_all_acks(tick, timeout),
+            Err(Error::TimedOut)
+        ));
+        // observed but not read.
+        assert!(watcher.has_changed());
+        watcher.ack();
+        assert!(matches!(
+            watched.wait_for_all_acks(tick, timeout),
+            Err(Error::TimedOut)
+        ));
+        watcher.read(|_| {});
+        watcher.ack();
+        assert!(matches!(
+            watched.wait_for_all_acks(tick, timeout),
+            Err(Error::TimedOut)
+        ));
+
+        // detached watcher does not block.
+        watched
+            .wait_for_all_acks(tick, Duration::from_secs(5))
+            .unwrap();
+        handle.join().unwrap();
+
+        // passed tick is acknowledged.
+        watched.write(3);
+        watched.write(4);
+        assert!(watcher.has_changed());
+        watcher.read(|_| {});
+        watcher.ack();
+        watched.wait_for_all_acks(tick, timeout).unwrap();
+
+        // tick of a write paused and not published yet.
+        watched.pause();
+        watched.write_with_callback(5, |t| tick = t);
+        assert!(matches!(
+            watched.wait_for_all_acks(tick, timeout),
+            Err(Error::TimedOut)
+        ));
+        watched.resume();
+        assert!(watcher.has_changed());
+        watcher.read(|_| {});
+        watcher.ack();
+        watched.wait_for_all_acks(tick, timeout).unwrap();
+    }
+
+    #[test]
+    fn wait_for_all_acks_window() {
+        let path = "./test_file53";
+        let timeout = Duration::from_millis(20);
+
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        // tick never written on a fresh segment.
+        watched.wait_for_all_acks(254, timeout).unwrap();
+
+        let mut mem2 = shared_memory_open(path, 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        let mut tick = 0;
+        watched.write_with_callback(0, |t| tick = t);
+        assert!(watcher.has_changed());
+        watcher.read(|_| {});
+        watcher.ack();
+
+        // acked write more than 64 writes ago.
+        for i in 1..100 {
+            watched.write(i);
+        }
+        watched.wait_for_all_acks(tick, timeout).unwrap();
+        let mut last = 0;
+        watched.write_with_callback(100, |t| last = t);
+        assert!(matches!(
+            watched.wait_for_all_acks(last, timeout),
+            Err(Error::TimedOut)
+        ));
+    }
+
+    #[test]
+    fn silent_watcher_after_wrap() {
+        let path = "./test_file50";
+        let timeout = Duration::from_millis(20);
+
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        for i in 0..70 {
+            watched.write(i);
+        }
+
+        // attached after the tick passed half of it's range and never acknowledging.
+        let mut mem2 = shared_memory_open(path, 1024).unwrap();
+        let _watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+
+        let mut tick = 0;
+        watched.write_with_callback(70, |t| tick = t);
+        assert!(matches!(
+            watched.wait_for_all_acks(tick, timeout),
+            Err(Error::TimedOut)
+        ));
+        // writes before construction are acknowledged.
+        let mut old = 0;
+        watched.write_with_callback(71, |t| old = t.wrapping_sub(4));
+        assert!(matches!(watched.wait_for_all_acks(old, timeout), Ok(())));
+    }
+
+    #[test]
+    fn stale_open_size() {
+        struct Truncated<'a>(&'a Shmem, usize);
+
+        impl Memory for Truncated<'_> {
+            fn as_ptr(&self) -> *mut u8 {
+                self.0.as_ptr()
+            }
+
+            fn len(&self) -> usize {
+                self.1
+            }
+        }
+
+        let path = "./test_file43";
+        let mut mem = shared_memory_create(path, 8192).unwrap();
+        let watched = Watched::<[u8; 4096]>::new_from_mem(&mut mem);
+        watched.write([1; 4096]);
+
+        // opened with a stale size.
+        let mut mem2 = shared_memory_open(path, 1024).unwrap();
+        assert!(mem2.len() >= 8192);
+        let watcher = Watcher::<[u8; 4096]>::new_from_mem(&mut mem2);
+        assert_eq!(watcher.read(|val| val[4095]), 1);
+        drop(watcher);
+
+        // mapping smaller than recorded layout is never read.
+        let truncated = Truncated(&mem2, 1024);
+        assert!(inspect::recorded_size(&truncated).unwrap() > 4096);
+        assert!(matches!(
+            Watcher::<[u8; 4096]>::try_from_memory(&truncated, None),
+            Err(Error::MemoryTooSmall { actual: 1024, .. })
+        ));
     }
 
     #[test]
@@ -0,0 +1,70 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+// Interval of checking watcher count in monitor thread.
+pub(crate) const MONITOR_INTERVAL: Duration = Duration::from_millis(10);
+
+// pointer to watcher count in shared memory.
+struct CountPtr(*const AtomicU32);
+
+// SAFETY:
+// The count is an atomic in shared memory and Monitor is stopped before memory is released.
+unsafe impl Send for CountPtr {}
+
+// A thread calling closure when watcher count in shared memory changed.
+// Thread is stopped and joined when Monitor is dropped.
+pub(crate) struct Monitor {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Monitor {
+    // SAFETY:
+    // Caller must make sure count is valid until returned Monitor is dropped.
+    pub(crate) unsafe fn spawn<F>(count: &AtomicU32, mut func: F) -> Self
+    where
+        F: FnMut(usize) + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut last = count.load(Ordering::SeqCst);
+        let ptr = CountPtr(count);
+        let handle = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                let ptr = ptr;
+                // SAFETY:
+                // count is valid until stop is set and this thread is joined.
+                let count = unsafe { &*ptr.0 };
+                while !stop.load(Ordering::SeqCst) {
+                    let current = count.load(Ordering::SeqCst);
+                    if current != last {
+                        last = current;
+                        func(current as usize);
+                    }
+                    thread::sleep(MONITOR_INTERVAL);
+                }
+            })
+        };
+
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            // panic of closure is already reported by the thread.
+            let _ = handle.join();
+        }
+    }
+}
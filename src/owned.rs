@@ -0,0 +1,143 @@
+use shared_memory::Shmem;
+
+use crate::{Error, FanOut, RwLockSync, SyncStrategy, Watched, Watcher};
+
+/// A [Watched] value owning it's shared memory. See [Watched::from_owned].
+pub type OwnedWatched<T, S = RwLockSync> = Watched<'static, T, S>;
+
+/// A [Watcher] owning it's shared memory. See [Watcher::from_owned].
+pub type OwnedWatcher<T, S = RwLockSync> = Watcher<'static, T, S>;
+
+// Shmem owned by a Watched or Watcher borrowing it for 'static.
+// It must be dropped after everything borrowing from it. Owners keep it as their last field
+// so it's dropped after their Drop impl (closing tick, uncounting watcher) and after all other
+// fields (monitor threads reading header) in declaration order.
+pub(crate) struct OwnedMem(*mut Shmem);
+
+impl OwnedMem {
+    pub(crate) fn new(mem: Shmem) -> Self {
+        Self(Box::into_raw(Box::new(mem)))
+    }
+
+    // SAFETY:
+    // Returned reference must not be used after Self is dropped.
+    pub(crate) unsafe fn get(&self) -> &'static mut Shmem {
+        &mut *self.0
+    }
+}
+
+impl Drop for OwnedMem {
+    fn drop(&mut self) {
+        // SAFETY:
+        // Pointer is from Box::into_raw and only freed here.
+        drop(unsafe { Box::from_raw(self.0) });
+    }
+}
+
+impl<T: Copy + Send + Sync, S: SyncStrategy> Watched<'static, T, S> {
+    /// Construct a new watched value taking ownership of given [Shmem].
+    /// See [Watched::try_from] for possible errors.
+    ///
+    /// The shared memory is dropped along with the value so the result is not tied to a
+    /// borrow and can be stored freely.
+    pub fn from_owned(mem: Shmem) -> Result<Self, Error> {
+        let mem = OwnedMem::new(mem);
+        // SAFETY:
+        // OwnedMem is stored in Watched and dropped after all other fields.
+        let mut watched = Watched::try_from(unsafe { mem.get() })?;
+        watched.2 = Some(mem);
+        Ok(watched)
+    }
+}
+
+impl<T: Copy + Send + Sync, S: SyncStrategy> Watcher<'static, T, S> {
+    /// Construct a new observer taking ownership of given [Shmem].
+    /// See [Watcher::try_from] for possible errors and [Watched::from_owned].
+    pub fn from_owned(mem: Shmem) -> Result<Self, Error> {
+        let mem = OwnedMem::new(mem);
+        // SAFETY:
+        // OwnedMem is stored in Watcher and dropped after all other fields.
+        let mut watcher = Watcher::try_from(unsafe { mem.get() })?;
+        watcher.mem = Some(mem);
+        Ok(watcher)
+    }
+
+    /// Move this watcher to a bridge thread forwarding every observed change to many in
+    /// process subscribers with queue of given capacity. See [FanOut].
+    pub fn fan_out(self, capacity: usize) -> FanOut<T>
+    where
+        T: 'static,
+        S: 'static,
+    {
+        FanOut::spawn(self, capacity)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{shared_memory_create, shared_memory_open};
+
+    #[test]
+    fn owned() {
+        let path = "./test_owned";
+
+        let watched =
+            OwnedWatched::<u8>::from_owned(shared_memory_create(path, 1024).unwrap()).unwrap();
+        let mut watcher =
+            OwnedWatcher::<u8>::from_owned(shared_memory_open(path, 1024).unwrap()).unwrap();
+
+        // owned values can be moved around freely.
+        let values = vec![watched];
+        let mut watchers = vec![watcher];
+        values[0].write(3);
+        watcher = watchers.pop().unwrap();
+        assert!(watcher.has_changed());
+        watcher.read(|val| assert_eq!(*val, 3));
+
+        drop(values);
+        assert!(watcher.writer_gone());
+        assert!(!std::path::Path::new(path).exists());
+    }
+
+    #[test]
+    fn owned_drop_order() {
+        let path = "./test_owned_drop";
+
+        for _ in 0..50 {
+            let watched =
+                OwnedWatched::<u64>::from_owned(shared_memory_create(path, 1024).unwrap()).unwrap();
+            // monitor reads header until it's joined on drop.
+            watched.on_watcher_count_change(|_| {});
+            let mut watcher =
+                OwnedWatcher::<u64>::from_owned(shared_memory_open(path, 1024).unwrap()).unwrap();
+            watched.write(1);
+            assert!(watcher.has_changed());
+
+            // close bit is written to shared memory before it's unmapped.
+            drop(watched);
+            assert!(watcher.writer_gone());
+            watcher.read(|val| assert_eq!(*val, 1));
+
+            // watcher count is decreased before it's unmapped.
+            drop(watcher);
+            assert!(!std::path::Path::new(path).exists());
+        }
+
+        // watcher dropped first.
+        let watched =
+            OwnedWatched::<u64>::from_owned(shared_memory_create(path, 1024).unwrap()).unwrap();
+        let watcher =
+            OwnedWatcher::<u64>::from_owned(shared_memory_open(path, 1024).unwrap()).unwrap();
+        assert_eq!(watched.watcher_count(), 1);
+        drop(watcher);
+        assert_eq!(watched.watcher_count(), 0);
+
+        // into_watcher hand over the owned memory.
+        let mut watcher = watched.into_watcher();
+        assert!(!watcher.has_changed());
+        drop(watcher);
+        assert!(!std::path::Path::new(path).exists());
+    }
+}
@@ -0,0 +1,19 @@
+/// Plain old data types can be safely viewed and restored as raw bytes.
+/// Required by [Watched::load_snapshot](crate::Watched::load_snapshot) and
+/// [Watcher::save_snapshot](crate::Watcher::save_snapshot).
+///
+/// # Safety
+///
+/// Implementor must have no padding bytes and every bit pattern of it's size must be a
+/// valid value. For example a `#[repr(C)]` struct of Pod fields without padding between them.
+pub unsafe trait Pod: Copy + Send + Sync + 'static {}
+
+macro_rules! pod {
+    ($($ty: ty),*) => {
+        $(unsafe impl Pod for $ty {})*
+    };
+}
+
+pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
@@ -0,0 +1,10 @@
+/// Outcome of [Watcher::poll_timeout](crate::Watcher::poll_timeout).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PollResult<T> {
+    /// Value changed. Contains a snapshot of the new value.
+    Changed(T),
+    /// [Watched](crate::Watched) value is gone.
+    Closed,
+    /// No change observed before the timeout.
+    TimedOut,
+}
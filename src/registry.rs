@@ -0,0 +1,158 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use shared_memory::ShmemConf;
+
+use crate::{shared_memory_create, shared_memory_open, Error, OwnedWatched, OwnedWatcher};
+
+// Default size of every shared memory in registry.
+const SEGMENT_SIZE: usize = 4096;
+
+/// A namespace of named values each placed in it's own shared memory.
+///
+/// The file link of value with name `name` is placed at `dir/name` and every shared memory
+/// is created with the same size (4096 bytes by default, see [Registry::with_size]).
+/// Processes using the same directory and size agree on all values in it.
+///
+/// A name must be a single file name so values never escape the directory. Names that are
+/// empty, `.`, `..` or contain `/` or `\\` are rejected with [Error::InvalidName].
+pub struct Registry {
+    dir: PathBuf,
+    size: usize,
+}
+
+impl Registry {
+    /// Construct a registry in given directory. The directory is created when missing.
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self, Error> {
+        fs::create_dir_all(dir.as_ref()).map_err(Error::Io)?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            size: SEGMENT_SIZE,
+        })
+    }
+
+    /// Set the size of every shared memory created or opened by this registry.
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Path to file link of value with given name.
+    ///
+    /// Return [Error::InvalidName] when `name` is not a single file name.
+    pub fn path(&self, name: &str) -> Result<PathBuf, Error> {
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(Error::InvalidName {
+                name: name.to_owned(),
+            });
+        }
+        Ok(self.dir.join(name))
+    }
+
+    /// Create a new shared memory for `name` and construct a watched value in it.
+    /// A previous shared memory with the same name is overwritten.
+    ///
+    /// The shared memory is owned by returned value and removed when it's dropped.
+    pub fn watched<T>(&self, name: &str) -> Result<OwnedWatched<T>, Error>
+    where
+        T: Copy + Send + Sync,
+    {
+        let mem = shared_memory_create(self.path(name)?, self.size)?;
+        OwnedWatched::from_owned(mem)
+    }
+
+    /// Open the shared memory of `name` and construct a watcher from it.
+    pub fn watcher<T>(&self, name: &str) -> Result<OwnedWatcher<T>, Error>
+    where
+        T: Copy + Send + Sync,
+    {
+        let mem = shared_memory_open(self.path(name)?, self.size)?;
+        OwnedWatcher::from_owned(mem)
+    }
+
+    /// Names of all values currently in this registry.
+    pub fn names(&self) -> Result<Vec<String>, Error> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&self.dir).map_err(Error::Io)? {
+            let entry = entry.map_err(Error::Io)?;
+            if entry.file_type().map_err(Error::Io)?.is_file() {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Remove all values in this registry and return the count of removed ones.
+    ///
+    /// Shared memory left by crashed processes is removed along with it's file link.
+    /// Values still in use keep working in processes already attached to them but are no
+    /// longer reachable by name.
+    pub fn clean(&self) -> Result<usize, Error> {
+        let names = self.names()?;
+        for name in names.iter() {
+            // names listed from the directory are file names.
+            let path = self.dir.join(name);
+            match ShmemConf::new().flink(&path).open() {
+                // owner removes both shared memory and file link on drop.
+                Ok(mut mem) => {
+                    mem.set_owner(true);
+                }
+                Err(_) => fs::remove_file(&path).map_err(Error::Io)?,
+            }
+        }
+        Ok(names.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn registry() {
+        let registry = Registry::new("./test_registry").unwrap();
+        registry.clean().unwrap();
+
+        let config = registry.watched::<u32>("config").unwrap();
+        let status = registry.watched::<u8>("status").unwrap();
+        assert_eq!(registry.names().unwrap(), ["config", "status"]);
+
+        let mut watcher = registry.watcher::<u32>("config").unwrap();
+        config.write(42);
+        assert!(watcher.has_changed());
+        watcher.read(|val| assert_eq!(*val, 42));
+
+        drop(status);
+        assert_eq!(registry.names().unwrap(), ["config"]);
+
+        // file link left by a value not dropped.
+        std::mem::forget(config);
+        assert_eq!(registry.clean().unwrap(), 1);
+        assert!(registry.names().unwrap().is_empty());
+        assert!(registry.watcher::<u32>("config").is_err());
+
+        fs::remove_dir("./test_registry").unwrap();
+    }
+
+    #[test]
+    fn registry_invalid_name() {
+        let registry = Registry::new("./test_registry_name").unwrap();
+        for name in ["", ".", "..", "../escaped", "a/b", "a\\b", "/abs"] {
+            assert!(matches!(
+                registry.watched::<u32>(name),
+                Err(Error::InvalidName { .. })
+            ));
+            assert!(matches!(
+                registry.watcher::<u32>(name),
+                Err(Error::InvalidName { .. })
+            ));
+        }
+        assert!(!Path::new("./escaped").exists());
+        assert!(registry.names().unwrap().is_empty());
+
+        fs::remove_dir("./test_registry_name").unwrap();
+    }
+}
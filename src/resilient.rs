@@ -0,0 +1,211 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::{shared_memory_open, OwnedWatcher};
+
+// Default interval between two reconnect attempts and health checks.
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A [Watcher](crate::Watcher) owning it's shared memory and recovering from
+/// [Watched](crate::Watched) restart.
+///
+/// The watcher disconnect from shared memory when any of the following is detected:
+/// - [Watched](crate::Watched) value is gone. The final value written before it's gone is
+///   still reported as change and the watcher disconnects after it's read.
+/// - The file link at path is missing or pointing to a different shared memory and no change
+///   is left unreported or unread.
+///   (A restarted [Watched](crate::Watched) always create a new shared memory.)
+///
+/// While disconnected [ResilientWatcher::has_changed] returns false and [ResilientWatcher::read]
+/// returns `None`. Reconnect is attempted on these calls with a fixed interval (100
+/// milliseconds by default, see [ResilientWatcher::with_retry_interval]) between attempts.
+/// Health check of the file link follows the same interval while connected.
+/// Value of the new [Watched](crate::Watched) is reported as change after reconnected.
+///
+/// A [Watched](crate::Watched) process crashed without dropping it's value can not be
+/// detected until a new one take over the path.
+pub struct ResilientWatcher<T: Copy + Send + Sync> {
+    path: PathBuf,
+    size: usize,
+    retry_interval: Duration,
+    last_check: Option<Instant>,
+    watcher: Option<OwnedWatcher<T>>,
+    // a change is reported by has_changed and not read yet.
+    unread: bool,
+    // Watched value is reported gone by connected watcher.
+    closed: bool,
+}
+
+impl<T: Copy + Send + Sync> ResilientWatcher<T> {
+    /// Construct a watcher of shared memory with given path and size.
+    /// Connection is attempted immediately and the watcher starts disconnected on failure.
+    pub fn new(path: impl AsRef<Path>, size: usize) -> Self {
+        let mut watcher = Self {
+            path: path.as_ref().to_path_buf(),
+            size,
+            retry_interval: RETRY_INTERVAL,
+            last_check: None,
+            watcher: None,
+            unread: false,
+            closed: false,
+        };
+        watcher.check();
+        watcher
+    }
+
+    /// Set the interval between reconnect attempts and health checks.
+    pub fn with_retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    /// Return true when watcher is connected to a [Watched](crate::Watched) value. It stays
+    /// connected to a gone value until it's final value is read.
+    pub fn is_connected(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    /// Observe the value change of T. Reconnect when needed.
+    /// See [Watcher::has_changed](crate::Watcher::has_changed).
+    pub fn has_changed(&mut self) -> bool {
+        self.check();
+
+        let watcher = match self.watcher {
+            Some(ref mut watcher) => watcher,
+            None => return false,
+        };
+
+        match watcher.try_has_changed() {
+            Ok(changed) => {
+                self.unread |= changed;
+                changed
+            }
+            Err(_) => {
+                self.closed = true;
+                false
+            }
+        }
+    }
+
+    /// Obtain a read lock and access &T through a closure. Reconnect when needed.
+    /// Return `None` when watcher is disconnected.
+    pub fn read<F, O>(&mut self, func: F) -> Option<O>
+    where
+        F: FnOnce(&T) -> O,
+    {
+        self.check();
+
+        let out = self.watcher.as_ref().map(|watcher| watcher.read(func));
+        self.unread = false;
+        out
+    }
+
+    // check the health of connection and try reconnect if it's due.
+    fn check(&mut self) {
+        if self.closed && !self.unread {
+            self.disconnect();
+        }
+
+        let now = Instant::now();
+        if let Some(last) = self.last_check {
+            if now.duration_since(last) < self.retry_interval {
+                return;
+            }
+        }
+        self.last_check = Some(now);
+
+        match self.watcher {
+            Some(ref watcher) => {
+                // a change left in replaced shared memory is still reported and read.
+                let pending = watcher.shared.tick.load().0 != watcher.tick;
+                if watcher.writer_gone() && !pending && !self.unread {
+                    self.disconnect();
+                }
+            }
+            None => {
+                if let Ok(watcher) = shared_memory_open(&self.path, self.size)
+                    .and_then(OwnedWatcher::<T>::from_owned)
+                {
+                    if watcher.shared.tick.try_get().is_some() {
+                        self.watcher = Some(watcher);
+                    }
+                }
+            }
+        }
+    }
+
+    fn disconnect(&mut self) {
+        self.watcher = None;
+        self.unread = false;
+        self.closed = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{shared_memory_create, Watched};
+
+    #[test]
+    fn reconnect() {
+        let path = "./test_resilient";
+
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        watched.write(1);
+
+        let mut watcher =
+            ResilientWatcher::<u8>::new(path, 1024).with_retry_interval(Duration::ZERO);
+        assert!(watcher.has_changed());
+        assert_eq!(watcher.read(|val| *val), Some(1));
+
+        drop(watched);
+        drop(mem);
+
+        assert!(!watcher.has_changed());
+        assert!(!watcher.is_connected());
+        assert_eq!(watcher.read(|val| *val), None);
+
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        watched.write(2);
+
+        assert!(watcher.has_changed());
+        assert!(watcher.is_connected());
+        assert_eq!(watcher.read(|val| *val), Some(2));
+    }
+
+    #[test]
+    fn final_value() {
+        let path = "./test_resilient2";
+
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        watched.write(1);
+
+        let mut watcher =
+            ResilientWatcher::<u8>::new(path, 1024).with_retry_interval(Duration::ZERO);
+        assert!(watcher.has_changed());
+        assert_eq!(watcher.read(|val| *val), Some(1));
+
+        watched.write(9);
+        drop(watched);
+
+        // final value is delivered before disconnect.
+        assert!(watcher.has_changed());
+        assert!(watcher.is_connected());
+        assert_eq!(watcher.read(|val| *val), Some(9));
+        assert!(!watcher.has_changed());
+        assert!(!watcher.has_changed());
+        assert!(!watcher.is_connected());
+
+        // reconnect to a new value in the same shared memory.
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+        watched.write(2);
+        assert!(watcher.has_changed());
+        assert_eq!(watcher.read(|val| *val), Some(2));
+    }
+}
@@ -1,68 +1,336 @@
-use std::{marker::PhantomData, mem};
+use std::{
+    cmp,
+    marker::PhantomData,
+    mem, ptr,
+    sync::atomic::{AtomicU8, Ordering},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-use raw_sync::locks::{LockImpl, LockInit, RwLock};
+use raw_sync::{
+    locks::{LockGuard, LockImpl},
+    Timeout,
+};
 use shared_memory::Shmem;
 
-use crate::tick::Tick;
+use crate::{
+    error::Error,
+    header::Header,
+    sync::{RwLockSync, SyncStrategy},
+    tick::{Tick, TICK_WIDTH},
+};
 
-pub(crate) struct Shared<'a, T: Copy> {
+// Step of touching memory in Shared::prefault. Smallest page size of supported platforms.
+const PAGE_SIZE: usize = 4096;
+
+// acquire a lock with attempt until timeout. None is waiting forever.
+//
+// raw_sync adds a relative timeout to the realtime clock without carrying nanoseconds into
+// seconds and the lock call fails right away when the sum reaches a full second. the wait is
+// split into whole seconds and sub-second slices that end before the clock's next second.
+fn timed<G>(timeout: Option<Duration>, mut attempt: impl FnMut(Timeout) -> Option<G>) -> Option<G> {
+    let Some(timeout) = timeout else {
+        return attempt(Timeout::Infinite);
+    };
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remain = deadline.saturating_duration_since(Instant::now());
+        let slice = if remain.as_secs() > 0 {
+            Duration::from_secs(remain.as_secs())
+        } else {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.subsec_nanos());
+            // margin for the clock moving between here and the lock call.
+            let room = Duration::from_nanos(u64::from(1_000_000_000 - nanos))
+                .saturating_sub(Duration::from_millis(1));
+            cmp::min(remain, room)
+        };
+        if let Some(guard) = attempt(Timeout::Val(slice)) {
+            return Some(guard);
+        }
+        if Instant::now() >= deadline {
+            return None;
+        }
+        if slice.is_zero() {
+            thread::yield_now();
+        }
+    }
+}
+
+// memory region Shared can be placed in.
+pub(crate) trait Memory {
+    // start of memory region. must be page aligned.
+    fn as_ptr(&self) -> *mut u8;
+
+    fn len(&self) -> usize;
+
+    // identity of memory region given by os. None when it has no name.
+    fn os_id(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl Memory for Shmem {
+    fn as_ptr(&self) -> *mut u8 {
+        Shmem::as_ptr(self)
+    }
+
+    fn len(&self) -> usize {
+        Shmem::len(self)
+    }
+
+    fn os_id(&self) -> Option<&str> {
+        Some(self.get_os_id())
+    }
+}
+
+pub(crate) struct Shared<'a, T: Copy, S: SyncStrategy = RwLockSync> {
+    pub(crate) header: &'a Header,
     pub(crate) tick: Tick<'a>,
     pub(crate) lock: Box<dyn LockImpl>,
-    data: PhantomData<T>,
+    // length of memory region starting from header.
+    len: usize,
+    os_id: Option<String>,
+    data: PhantomData<(T, S)>,
 }
 
-impl<'a, T: Copy> Shared<'a, T> {
-    pub(crate) fn new_from_mem(mem: &'a Shmem) -> Self {
-        Self::from_mem(mem, |ptr, data_off| unsafe {
-            // SAFETY:
-            // Trust the pointer given by Shmem and data_off counted the size of RwLock.
+impl<'a, T: Copy, S: SyncStrategy> Shared<'a, T, S> {
+    // obtain a read lock and access &T through a closure.
+    pub(crate) fn read<F, O>(&self, func: F) -> O
+    where
+        F: FnOnce(&T) -> O,
+    {
+        let guard = self.lock.rlock().unwrap();
+        // SAFETY:
+        // Watcher<T> and Watched<T> type are the only type constructor expose.
+        let val = unsafe { mem::transmute::<&u8, &T>(&**guard) };
+        func(val)
+    }
 
-            let (lock, _) = RwLock::new(ptr, ptr.add(data_off)).unwrap();
-            lock
-        })
+    // try to obtain a read lock until timeout and access &T through a closure.
+    // return None when the lock is held by writer. zero timeout never blocks.
+    pub(crate) fn try_read<F, O>(&self, timeout: Option<Duration>, func: F) -> Option<O>
+    where
+        F: FnOnce(&T) -> O,
+    {
+        let guard = timed(timeout, |t| self.lock.try_rlock(t).ok())?;
+        // SAFETY:
+        // Watcher<T> and Watched<T> type are the only type constructor expose.
+        let val = unsafe { mem::transmute::<&u8, &T>(&**guard) };
+        Some(func(val))
+    }
+
+    // try to obtain the write lock until timeout. None is waiting forever.
+    pub(crate) fn try_lock(&self, timeout: Option<Duration>) -> Option<LockGuard<'_>> {
+        timed(timeout, |t| self.lock.try_lock(t).ok())
     }
 
-    pub(crate) fn exist_from_mem(mem: &'a Shmem) -> Self {
-        Self::from_mem(mem, |ptr, data_off| unsafe {
+    // check if both are attached to the same memory region. os id is compared when both have
+    // one and address of header otherwise.
+    pub(crate) fn is_same(&self, other: &Self) -> bool {
+        match (&self.os_id, &other.os_id) {
+            (Some(id), Some(other)) => id == other,
+            _ => ptr::eq(self.header, other.header),
+        }
+    }
+
+    // touch every page of memory region with a write that does not change it's content.
+    pub(crate) fn prefault(&self) {
+        let base = self.header as *const Header as *const u8;
+        for off in (0..self.len).step_by(PAGE_SIZE) {
             // SAFETY:
-            // Trust the pointer given by Shmem and data_off counted the size of RwLock.
-            let (lock, _) = RwLock::from_existing(ptr, ptr.add(data_off)).unwrap();
-            lock
-        })
+            // off is in range of memory region. Adding zero atomically never changes the
+            // content even when it's accessed by others concurrently.
+            unsafe { (*(base.add(off) as *const AtomicU8)).fetch_add(0, Ordering::Relaxed) };
+        }
     }
 
-    // create Shared with a closure for rwlock constructing.
-    fn from_mem<F>(mem: &'a Shmem, func: F) -> Self
-    where
-        F: FnOnce(*mut u8, usize) -> Box<dyn LockImpl>,
-    {
-        // Check for the size of shared memory.
-        let shared_size = mem::size_of::<Self>();
-        let mem_size = mem.len();
-        assert!(
-            shared_size <= mem_size,
-            "Shared memory not enough, {} extra bytes needed",
-            shared_size - mem_size
-        );
+    // reconstruct lock in place.
+    // SAFETY:
+    // Caller must make sure no one is holding or waiting for the lock.
+    pub(crate) unsafe fn reinit_lock(&mut self) {
+        let ptr = self.lock.as_raw() as *mut u8;
+        let data = *self.lock.get_inner();
+        self.lock = S::init_lock(ptr, data);
+    }
+
+    // attach to shared memory. lock is constructed by the first one attached to it.
+    pub(crate) fn try_from_mem<M: Memory>(mem: &'a M) -> Result<Self, Error> {
+        Self::try_from_mem_aligned(mem, 1)
+    }
 
-        let mut ptr = mem.as_ptr();
+    // attach to shared memory with data aligned to at least data_align bytes.
+    // the data offset is decided by the first one attached and recorded in header.
+    pub(crate) fn try_from_mem_aligned<M: Memory>(
+        mem: &'a M,
+        data_align: usize,
+    ) -> Result<Self, Error> {
+        let base = mem.as_ptr();
 
         // SAFETY:
-        // Shmem is borrowed for the same lifetime of Self so Tick's lifetime is satisfied.
-        let tick = unsafe {
-            let (tick, size) = Tick::from_ptr(ptr);
-            ptr = ptr.add(size);
-            tick
-        };
+        // Memory is borrowed for the same lifetime of Self so Header's lifetime is satisfied.
+        // Memory pointer is page aligned.
+        let (header, header_size) = unsafe { Header::from_ptr(base) };
+
+        // lock is placed after header.
+        let lock_ptr = base.wrapping_add(header_size);
+
+        let align = cmp::max(mem::align_of::<T>(), data_align);
+        let (_, data_off) = Self::layout(base, align);
+
+        Self::check_size(mem.len(), data_off)?;
 
-        let data_off = RwLock::size_of(Some(ptr));
+        // SAFETY:
+        // Trust the pointer given by Memory and data_off counted the size of lock.
+        header.init_once(|| unsafe {
+            header.data_off.store(data_off as u32, Ordering::SeqCst);
+            header.tick_width.store(TICK_WIDTH, Ordering::SeqCst);
+            header.sync.store(S::ID, Ordering::SeqCst);
+            header
+                .value_size
+                .store(mem::size_of::<T>() as u32, Ordering::SeqCst);
+            header
+                .value_align
+                .store(mem::align_of::<T>() as u32, Ordering::SeqCst);
+            S::init_lock(lock_ptr, base.add(data_off));
+        })?;
+
+        // shared memory could be initialized by others with a different alignment.
+        let data_off = Self::check_header(header, base, mem.len(), align)?;
 
-        let lock = func(ptr, data_off);
+        // SAFETY:
+        // data_off is checked to be in range of Memory.
+        let lock = unsafe { S::attach_lock(lock_ptr, base.add(data_off)) };
 
-        Self {
-            tick,
+        Ok(Self {
+            header,
+            tick: Tick::new(&header.tick),
             lock,
+            len: mem.len(),
+            os_id: mem.os_id().map(str::to_string),
             data: PhantomData,
+        })
+    }
+}
+
+impl<T: Copy, S: SyncStrategy> Shared<'_, T, S> {
+    // offset of lock end and data for shared memory starting at base.
+    // lock is placed after header and data is placed after lock aligned to align.
+    fn layout(base: *mut u8, align: usize) -> (usize, usize) {
+        let header_size = mem::size_of::<Header>();
+        let lock_end = header_size + S::lock_size(base.wrapping_add(header_size));
+        let data_off = lock_end + base.wrapping_add(lock_end).align_offset(align);
+        (lock_end, data_off)
+    }
+
+    // check layout recorded in initialized header is valid for T and return data offset.
+    fn check_header(
+        header: &Header,
+        base: *mut u8,
+        len: usize,
+        align: usize,
+    ) -> Result<usize, Error> {
+        let tick_width = header.tick_width.load(Ordering::SeqCst);
+        if tick_width != TICK_WIDTH {
+            return Err(Error::TickWidthMismatch {
+                expected: TICK_WIDTH,
+                found: tick_width,
+            });
+        }
+
+        let sync = header.sync.load(Ordering::SeqCst);
+        if sync != S::ID {
+            return Err(Error::SyncMismatch {
+                expected: S::ID,
+                found: sync,
+            });
+        }
+
+        let writer_size = header.value_size.load(Ordering::SeqCst) as usize;
+        let writer_align = header.value_align.load(Ordering::SeqCst) as usize;
+        if writer_size != mem::size_of::<T>() || writer_align != mem::align_of::<T>() {
+            return Err(Error::LayoutMismatch {
+                writer_size,
+                reader_size: mem::size_of::<T>(),
+                writer_align,
+                reader_align: mem::align_of::<T>(),
+            });
+        }
+
+        let data_off = header.data_off.load(Ordering::SeqCst) as usize;
+        let (lock_end, _) = Self::layout(base, align);
+        if data_off < lock_end {
+            return Err(Error::InvalidLayout {
+                reason: "data overlaps lock",
+            });
+        }
+
+        Self::check_size(len, data_off)?;
+
+        if base.wrapping_add(data_off).align_offset(align) != 0 {
+            return Err(Error::Misaligned { align });
+        }
+
+        Ok(data_off)
+    }
+
+    // check layout of a shared memory with given size for T without attaching to it.
+    // shared memory is assumed to be page aligned.
+    pub(crate) fn validate_layout(len: usize) -> Result<(), Error> {
+        let base = ptr::null_mut::<u8>();
+        let (lock_end, data_off) = Self::layout(base, mem::align_of::<T>());
+        if data_off > u32::MAX as usize || mem::size_of::<T>() > u32::MAX as usize {
+            return Err(Error::InvalidLayout {
+                reason: "value too large",
+            });
+        }
+        debug_assert!(data_off >= lock_end);
+        Self::check_size(len, data_off)
+    }
+
+    // check offsets of tick, lock and data of attached shared memory are consistent.
+    pub(crate) fn self_check(&self) -> Result<(), Error> {
+        let base = self.header as *const Header as *mut u8;
+        let header_size = mem::size_of::<Header>();
+        let data_off = Self::check_header(self.header, base, self.len, mem::align_of::<T>())?;
+
+        let tick = &self.header.tick as *const _ as usize - base as usize;
+        if tick >= header_size {
+            return Err(Error::InvalidLayout {
+                reason: "tick outside of header",
+            });
+        }
+
+        let (lock_end, _) = Self::layout(base, 1);
+        let lock = self.lock.as_raw() as usize - base as usize;
+        if lock < header_size || lock >= lock_end {
+            return Err(Error::InvalidLayout {
+                reason: "lock outside of lock region",
+            });
+        }
+
+        // SAFETY:
+        // data pointer of lock is only read.
+        let data = unsafe { *self.lock.get_inner() } as usize;
+        if data != base as usize + data_off {
+            return Err(Error::InvalidLayout {
+                reason: "lock guards data at wrong offset",
+            });
+        }
+
+        Ok(())
+    }
+
+    // check shared memory is large enough for T placed at data_off.
+    fn check_size(mem_size: usize, data_off: usize) -> Result<(), Error> {
+        let shared_size = data_off + mem::size_of::<T>();
+        if shared_size > mem_size {
+            return Err(Error::MemoryTooSmall {
+                required: shared_size,
+                actual: mem_size,
+            });
         }
+        Ok(())
     }
 }
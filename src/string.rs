@@ -0,0 +1,249 @@
+use std::str;
+
+use shared_memory::Shmem;
+
+use crate::{Error, Watched, Watcher};
+
+// Length prefixed UTF-8 bytes.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct StrBuf<const CAP: usize> {
+    len: usize,
+    bytes: [u8; CAP],
+}
+
+impl<const CAP: usize> StrBuf<CAP> {
+    // Valid UTF-8 prefix of stored bytes. Only a foreign writer can store invalid bytes and
+    // they are not handed out.
+    fn as_str(&self) -> &str {
+        let bytes = &self.bytes[..self.len.min(CAP)];
+        match str::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(e) => str::from_utf8(&bytes[..e.valid_up_to()]).unwrap(),
+        }
+    }
+}
+
+/// A string with capacity of `CAP` bytes shared between processes.
+///
+/// It's a [Watched] of fixed size UTF-8 byte array with a length prefix and observed by
+/// [WatcherString] with the same `CAP`.
+///
+/// Length and bytes are written together under the write lock so a reader never observes a
+/// length not matching the bytes.
+pub struct WatchedString<'a, const CAP: usize>(Watched<'a, StrBuf<CAP>>);
+
+impl<'a, const CAP: usize> WatchedString<'a, CAP> {
+    /// Construct a new watched string in given [Shmem]. The initial value is empty.
+    /// See [Watched::new_from_mem].
+    ///
+    /// # panics:
+    ///
+    /// When [Shmem] is not large enough for the string.
+    pub fn new_from_mem(mem: &'a mut Shmem) -> Self {
+        Self::try_from(mem).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Obtain a write lock and write a new string.
+    ///
+    /// Return [Error::TooLong] when `value` is longer than `CAP` bytes.
+    pub fn write(&self, value: &str) -> Result<(), Error> {
+        let len = value.len();
+        if len > CAP {
+            return Err(Error::TooLong { len, cap: CAP });
+        }
+
+        let mut buf = StrBuf {
+            len,
+            bytes: [0; CAP],
+        };
+        buf.bytes[..len].copy_from_slice(value.as_bytes());
+        self.0.write(buf);
+        Ok(())
+    }
+
+    /// Obtain a read lock and access the current string through a closure.
+    pub fn read<F, O>(&self, func: F) -> O
+    where
+        F: FnOnce(&str) -> O,
+    {
+        self.0 .0.read(|buf| func(buf.as_str()))
+    }
+
+    /// Obtain a read lock and return the length of current string in bytes. The length is
+    /// read with the bytes so it always matches the string given by [WatchedString::read].
+    pub fn len(&self) -> usize {
+        self.read(str::len)
+    }
+
+    /// Check if current string is empty. See [WatchedString::len].
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Max length of string in bytes. That is `CAP`.
+    pub fn capacity(&self) -> usize {
+        CAP
+    }
+}
+
+/// Return [Error::MemoryTooSmall] when [Shmem] is not large enough for the string.
+impl<'a, const CAP: usize> TryFrom<&'a mut Shmem> for WatchedString<'a, CAP> {
+    type Error = Error;
+
+    fn try_from(mem: &'a mut Shmem) -> Result<Self, Self::Error> {
+        let watched = Watched::try_from(mem)?;
+        watched.write(StrBuf {
+            len: 0,
+            bytes: [0; CAP],
+        });
+        Ok(Self(watched))
+    }
+}
+
+/// Reader of a [WatchedString] with the same `CAP`.
+pub struct WatcherString<'a, const CAP: usize>(Watcher<'a, StrBuf<CAP>>);
+
+impl<'a, const CAP: usize> WatcherString<'a, CAP> {
+    /// Construct a new observer of string from given [Shmem]. See [Watcher::new_from_mem].
+    ///
+    /// # panics:
+    ///
+    /// When [Shmem] is not large enough for the string.
+    pub fn new_from_mem(mem: &'a mut Shmem) -> Self {
+        Self::try_from(mem).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Observe the change of string. See [Watcher::has_changed].
+    pub fn has_changed(&mut self) -> bool {
+        self.0.has_changed()
+    }
+
+    /// Block current thread until the string changed. See [Watcher::wait_for_change].
+    pub fn wait_for_change(&mut self) -> Result<(), Error> {
+        self.0.wait_for_change()
+    }
+
+    /// Obtain a read lock and access the current string through a closure.
+    ///
+    /// The stored bytes are validated as UTF-8 before handed to closure.
+    pub fn read<F, O>(&self, func: F) -> O
+    where
+        F: FnOnce(&str) -> O,
+    {
+        self.0.read(|buf| func(buf.as_str()))
+    }
+
+    /// Obtain a read lock and return the length of current string in bytes. The length is
+    /// read with the bytes so it always matches the string given by [WatcherString::read].
+    pub fn len(&self) -> usize {
+        self.read(str::len)
+    }
+
+    /// Check if current string is empty. See [WatcherString::len].
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Max length of string in bytes. That is `CAP`.
+    pub fn capacity(&self) -> usize {
+        CAP
+    }
+}
+
+/// Return [Error::MemoryTooSmall] when [Shmem] is not large enough for the string.
+impl<'a, const CAP: usize> TryFrom<&'a mut Shmem> for WatcherString<'a, CAP> {
+    type Error = Error;
+
+    fn try_from(mem: &'a mut Shmem) -> Result<Self, Self::Error> {
+        Watcher::try_from(mem).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{shared_memory_create, shared_memory_open};
+
+    #[test]
+    fn string() {
+        let path = "./test_string";
+
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = WatchedString::<8>::new_from_mem(&mut mem);
+        let mut mem2 = shared_memory_open(path, 1024).unwrap();
+        let mut watcher = WatcherString::<8>::new_from_mem(&mut mem2);
+
+        watcher.read(|s| assert_eq!(s, ""));
+        assert!(watcher.is_empty());
+        assert_eq!(watcher.capacity(), 8);
+        assert_eq!(watched.capacity(), 8);
+
+        // 3 bytes each. 2 of them fit and the third one exceeds capacity by 1 byte.
+        watched.write("ここ").unwrap();
+        assert!(watcher.has_changed());
+        watcher.read(|s| assert_eq!(s, "ここ"));
+
+        assert!(matches!(
+            watched.write("ここに"),
+            Err(Error::TooLong { len: 9, cap: 8 })
+        ));
+        assert!(!watcher.has_changed());
+        watcher.read(|s| assert_eq!(s, "ここ"));
+
+        // exactly at capacity.
+        watched.write("éé€").unwrap();
+        assert!(watcher.has_changed());
+        watcher.read(|s| assert_eq!(s, "éé€"));
+        watched.read(|s| assert_eq!(s.len(), 7));
+        assert_eq!(watched.len(), 7);
+        assert_eq!(watcher.len(), 7);
+
+        watched.write("12345678").unwrap();
+        assert!(watcher.has_changed());
+        watcher.read(|s| assert_eq!(s, "12345678"));
+    }
+
+    #[test]
+    fn torn_string() {
+        let path = "./test_string_torn";
+        const LONG: &str = "éééééééééééééééééééééééééééééé€";
+        const SHORT: &str = "€";
+
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = WatchedString::<64>::new_from_mem(&mut mem);
+        watched.write(SHORT).unwrap();
+
+        let handle = std::thread::spawn(move || {
+            let mut mem = shared_memory_open(path, 1024).unwrap();
+            let mut watcher = WatcherString::<64>::new_from_mem(&mut mem);
+            let mut reads = 0;
+            while watcher.wait_for_change().is_ok() {
+                let done = watcher.read(|s| {
+                    assert!(s == LONG || s == SHORT || s == "done", "torn read: {:?}", s);
+                    s == "done"
+                });
+                let len = watcher.len();
+                assert!(
+                    [LONG.len(), SHORT.len(), 4].contains(&len),
+                    "torn len: {}",
+                    len
+                );
+                reads += 1;
+                if done {
+                    break;
+                }
+            }
+            reads
+        });
+
+        for i in 0..20_000 {
+            watched
+                .write(if i % 2 == 0 { LONG } else { SHORT })
+                .unwrap();
+        }
+        watched.write("done").unwrap();
+        assert!(handle.join().unwrap() > 0);
+    }
+}
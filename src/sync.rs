@@ -0,0 +1,69 @@
+//! Lock types guarding shared value.
+
+use raw_sync::locks::{LockImpl, LockInit, Mutex, RwLock};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Type of lock guarding the value of [Watched](crate::Watched) and
+/// [Watcher](crate::Watcher) selected at compile time by type parameter.
+///
+/// It only selects the lock placed in shared memory. Reads, writes and tick advance are the
+/// same for every strategy: a read holds the shared side of lock and a write holds the
+/// exclusive side while writing value and advancing tick. So watchers never observe a tick
+/// without it's value. Lock free strategies like seqlock or double buffer can't be expressed
+/// with it and the trait is sealed.
+///
+/// Strategy is recorded in header of shared memory. Attaching with a different strategy
+/// returns [Error::SyncMismatch](crate::Error::SyncMismatch).
+pub trait SyncStrategy: sealed::Sealed {
+    #[doc(hidden)]
+    const ID: u8;
+
+    #[doc(hidden)]
+    fn lock_size(ptr: *mut u8) -> usize;
+
+    #[doc(hidden)]
+    /// # Safety
+    /// ptr and data must be valid for the lifetime of returned lock.
+    unsafe fn init_lock(ptr: *mut u8, data: *mut u8) -> Box<dyn LockImpl>;
+
+    #[doc(hidden)]
+    /// # Safety
+    /// ptr and data must be valid for the lifetime of returned lock and ptr must point to a
+    /// lock constructed by [SyncStrategy::init_lock].
+    unsafe fn attach_lock(ptr: *mut u8, data: *mut u8) -> Box<dyn LockImpl>;
+}
+
+/// Readers share access to value and writer has exclusive access. The default strategy.
+pub struct RwLockSync;
+
+/// All readers and writer have exclusive access to value. Useful when readers are few and
+/// platform rwlock is unfair to writer.
+pub struct MutexSync;
+
+macro_rules! strategy {
+    ($ty: ty, $lock: ty, $id: literal) => {
+        impl sealed::Sealed for $ty {}
+
+        impl SyncStrategy for $ty {
+            const ID: u8 = $id;
+
+            fn lock_size(ptr: *mut u8) -> usize {
+                <$lock>::size_of(Some(ptr))
+            }
+
+            unsafe fn init_lock(ptr: *mut u8, data: *mut u8) -> Box<dyn LockImpl> {
+                <$lock>::new(ptr, data).unwrap().0
+            }
+
+            unsafe fn attach_lock(ptr: *mut u8, data: *mut u8) -> Box<dyn LockImpl> {
+                <$lock>::from_existing(ptr, data).unwrap().0
+            }
+        }
+    };
+}
+
+strategy!(RwLockSync, RwLock, 1);
+strategy!(MutexSync, Mutex, 2);
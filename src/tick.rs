@@ -1,36 +1,52 @@
-use std::{
-    mem,
-    sync::atomic::{AtomicU8, Ordering},
-};
+use std::sync::atomic::{AtomicU8, Ordering};
 
-// important. Tick must have the same layout of inner atomic counter.
-#[repr(transparent)]
-pub(crate) struct Tick<'a>(&'a mut AtomicU8);
+pub(crate) struct Tick<'a>(&'a AtomicU8);
+
+// Width of tick counter in bytes. Recorded in header so processes agree on it.
+pub(crate) const TICK_WIDTH: u8 = std::mem::size_of::<AtomicU8>() as u8;
 
 // The last bit of tick is used to mark the existence of active watcher
 const TICK: u8 = 1 << 1;
 
 impl<'a> Tick<'a> {
-    // SAFETY:
-    // Caller must make sure given pointer is valid for the lifetime of Tick.
-    pub(crate) unsafe fn from_ptr(ptr: *mut u8) -> (Self, usize) {
-        let atomic_size = mem::size_of::<Self>();
-        let tick = Tick(&mut *(ptr as *mut AtomicU8));
-
-        (tick, atomic_size)
+    pub(crate) fn new(counter: &'a AtomicU8) -> Self {
+        Tick(counter)
     }
 
     pub(crate) fn tick(&self) {
         self.0.fetch_add(TICK, Ordering::SeqCst);
     }
 
+    // the tick value would be observed after next tick call.
+    pub(crate) fn next(&self) -> u8 {
+        self.0.load(Ordering::SeqCst).wrapping_add(TICK)
+    }
+
     pub(crate) fn store(&self, val: u8) {
         self.0.store(val, Ordering::SeqCst);
     }
 
     pub(crate) fn close(&self) {
-        let val = self.try_get().unwrap();
-        self.store(val | 1);
+        self.0.fetch_or(1, Ordering::SeqCst);
+    }
+
+    // count the number of tick happened between two observed tick value.
+    // the counter is wrapping so distance is always in range of 0..128.
+    pub(crate) fn distance(old: u8, new: u8) -> u8 {
+        new.wrapping_sub(old) / TICK
+    }
+
+    // check if observed tick value reach or pass the target in wrapping order.
+    // target more than half of the counter range ahead is treated as passed.
+    pub(crate) fn reached(current: u8, target: u8) -> bool {
+        (target.wrapping_sub(current) as i8) <= 0
+    }
+
+    // load tick value along with the close bit.
+    // tick value is kept after closed so the last tick advance can still be observed.
+    pub(crate) fn load(&self) -> (u8, bool) {
+        let val = self.0.load(Ordering::SeqCst);
+        (val & !1, val & 1 == 1)
     }
 
     pub(crate) fn try_get(&self) -> Option<u8> {
@@ -42,3 +58,40 @@ impl<'a> Tick<'a> {
         }
     }
 }
+
+// Count of recent observed tick values kept by TickWindow.
+const WINDOW: usize = 4;
+
+// A small window of tick values recently observed by a watcher.
+pub(crate) struct TickWindow {
+    ticks: [u8; WINDOW],
+    next: usize,
+}
+
+impl TickWindow {
+    pub(crate) fn new() -> Self {
+        Self {
+            ticks: [0; WINDOW],
+            next: 1,
+        }
+    }
+
+    pub(crate) fn push(&mut self, tick: u8) {
+        self.ticks[self.next % WINDOW] = tick;
+        self.next += 1;
+    }
+
+    // the last pushed tick value.
+    pub(crate) fn last(&self) -> u8 {
+        self.ticks[(self.next - 1) % WINDOW]
+    }
+
+    // check if new tick value goes backward from the last pushed one.
+    //
+    // a wrapping counter going from 254 to 0 is moving forward and not backward.
+    // tick is treated as backward when it's behind the last one in wrapping order and it's
+    // one of the recent observed tick values.
+    pub(crate) fn is_backward(&self, tick: u8) -> bool {
+        (tick.wrapping_sub(self.last()) as i8) < 0 && self.ticks.contains(&tick)
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    array, mem,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use shared_memory::Shmem;
+
+use crate::{Error, RwLockSync, SyncStrategy, Watched, Watcher};
+
+// Max time of waiting for write lock to clear the window on construction. No default timeout
+// is set yet so it's bounded here instead of blocking on a lock held by a crashed process.
+const CLEAR_TIMEOUT: Duration = Duration::from_secs(1);
+
+// ring of recent entries in shared memory. next is the count of appended entries.
+#[derive(Clone, Copy)]
+pub(crate) struct Trend<T, const K: usize> {
+    next: u64,
+    entries: [(T, u64); K],
+}
+
+/// Writer of the last `K` values of `T` along with the time they are appended. It's for
+/// displaying recent trend of a value like a sparkline.
+///
+/// Each entry is timestamped with microseconds since unix epoch of system clock. Entries are
+/// kept in a ring so appending more than `K` values overwrites the oldest one. Shared memory
+/// is taking `K * (size_of::<T>() + 8)` bytes for entries plus padding and an 8 bytes
+/// counter.
+pub struct WatchedTrend<'a, T, const K: usize, S = RwLockSync>(Watched<'a, Trend<T, K>, S>)
+where
+    T: Copy + Send + Sync,
+    S: SyncStrategy;
+
+impl<'a, T, const K: usize, S> WatchedTrend<'a, T, K, S>
+where
+    T: Copy + Send + Sync + Default,
+    S: SyncStrategy,
+{
+    /// Construct a new trend in given [Shmem] with an empty window. See
+    /// [Watched::new_from_mem].
+    ///
+    /// # panics:
+    ///
+    /// When [Shmem] is not valid for `K` entries of `T` or `K` is zero.
+    pub fn new_from_mem(mem: &'a mut Shmem) -> Self {
+        Self::try_from(mem).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Obtain a write lock and append `value` timestamped with current time. Watchers are
+    /// notified once.
+    pub fn append(&self, value: T) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+        self.0.modify(|_, trend| {
+            trend.entries[(trend.next % K as u64) as usize] = (value, now);
+            trend.next += 1;
+        });
+    }
+}
+
+/// Return [Error::MemoryTooSmall] when [Shmem] is not large enough for `K` entries of `T` and
+/// [Error::TimedOut] when the write lock for clearing the window is not obtained in 1 second.
+impl<'a, T, const K: usize, S> TryFrom<&'a mut Shmem> for WatchedTrend<'a, T, K, S>
+where
+    T: Copy + Send + Sync + Default,
+    S: SyncStrategy,
+{
+    type Error = Error;
+
+    fn try_from(mem: &'a mut Shmem) -> Result<Self, Self::Error> {
+        assert!(K > 0, "trend of zero entries");
+        let watched = Watched::try_from(mem)?;
+        {
+            // window of previous writer is cleared without notifying watchers.
+            let mut guard = watched
+                .0
+                .try_lock(Some(CLEAR_TIMEOUT))
+                .ok_or(Error::TimedOut)?;
+            // SAFETY:
+            // Watched of the same type is the only one writing shared memory.
+            let trend = unsafe { mem::transmute::<&mut u8, &mut Trend<T, K>>(&mut **guard) };
+            *trend = Trend {
+                next: 0,
+                entries: [(T::default(), 0); K],
+            };
+        }
+        Ok(Self(watched))
+    }
+}
+
+/// Reader of a [WatchedTrend] with the same `T` and `K`.
+pub struct WatcherTrend<'a, T, const K: usize, S = RwLockSync>(Watcher<'a, Trend<T, K>, S>)
+where
+    T: Copy + Send + Sync,
+    S: SyncStrategy;
+
+impl<'a, T, const K: usize, S> WatcherTrend<'a, T, K, S>
+where
+    T: Copy + Send + Sync,
+    S: SyncStrategy,
+{
+    /// Construct a new observer of trend from given [Shmem]. See [Watcher::new_from_mem].
+    ///
+    /// # panics:
+    ///
+    /// When [Shmem] is not valid for `K` entries of `T`.
+    pub fn new_from_mem(mem: &'a mut Shmem) -> Self {
+        Self::try_from(mem).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Observe a new append. See [Watcher::has_changed].
+    pub fn has_changed(&mut self) -> bool {
+        self.0.has_changed()
+    }
+
+    /// Block current thread until a new append. See [Watcher::wait_for_change].
+    pub fn wait_for_change(&mut self) -> Result<(), Error> {
+        self.0.wait_for_change()
+    }
+
+    /// Obtain a read lock and copy the recent window of values and timestamps from the oldest
+    /// to the newest.
+    ///
+    /// Until `K` values are appended the window starts with entries of default value and a
+    /// timestamp of 0.
+    pub fn history(&self) -> [(T, u64); K] {
+        self.0.read(|trend| {
+            let start = (trend.next % K as u64) as usize;
+            array::from_fn(|i| trend.entries[(start + i) % K])
+        })
+    }
+}
+
+/// Return [Error::MemoryTooSmall] when [Shmem] is not large enough for `K` entries of `T`.
+impl<'a, T, const K: usize, S> TryFrom<&'a mut Shmem> for WatcherTrend<'a, T, K, S>
+where
+    T: Copy + Send + Sync,
+    S: SyncStrategy,
+{
+    type Error = Error;
+
+    fn try_from(mem: &'a mut Shmem) -> Result<Self, Self::Error> {
+        Watcher::try_from(mem).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{shared_memory_create, shared_memory_open};
+
+    #[test]
+    fn history() {
+        let path = "./test_trend";
+
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = WatchedTrend::<u32, 3>::new_from_mem(&mut mem);
+
+        let mut mem2 = shared_memory_open(path, 1024).unwrap();
+        let mut watcher = WatcherTrend::<u32, 3>::new_from_mem(&mut mem2);
+        assert!(!watcher.has_changed());
+        assert_eq!(watcher.history(), [(0, 0); 3]);
+
+        watched.append(1);
+        assert!(watcher.has_changed());
+        let history = watcher.history();
+        assert_eq!(history.map(|(val, _)| val), [0, 0, 1]);
+        assert_eq!(history[0].1, 0);
+        assert!(history[2].1 > 0);
+
+        for i in 2..=5 {
+            watched.append(i);
+        }
+        let history = watcher.history();
+        assert_eq!(history.map(|(val, _)| val), [3, 4, 5]);
+    }
+}
@@ -0,0 +1,87 @@
+use std::sync::atomic::Ordering;
+
+use crate::{RwLockSync, SyncStrategy, Watcher};
+
+/// Blocking iterator of values observed by a [Watcher]. Constructed by [Watcher::updates] and
+/// [Watcher::updates_with_current].
+///
+/// Each item is a snapshot read right after a change is observed. Values overwritten before
+/// being observed are skipped and counted by [Watcher::overruns]. The iterator ends when
+/// [Watched](crate::Watched) value is gone.
+pub struct Updates<'w, 'a, T, S = RwLockSync>
+where
+    T: Copy + Send + Sync,
+    S: SyncStrategy,
+{
+    watcher: &'w mut Watcher<'a, T, S>,
+    current: Option<T>,
+}
+
+impl<T: Copy + Send + Sync, S: SyncStrategy> Iterator for Updates<'_, '_, T, S> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if let Some(val) = self.current.take() {
+            return Some(val);
+        }
+        self.watcher.wait_for_change().ok()?;
+        Some(self.watcher.read(|val| *val))
+    }
+}
+
+impl<'a, T: Copy + Send + Sync, S: SyncStrategy> Watcher<'a, T, S> {
+    /// Iterate over future changes of value. See [Updates].
+    pub fn updates(&mut self) -> Updates<'_, 'a, T, S> {
+        Updates {
+            watcher: self,
+            current: None,
+        }
+    }
+
+    /// Same as [Watcher::updates] but yield the current value as the first item when any
+    /// value is written. A pending change is treated as observed so the current value is
+    /// not yielded twice.
+    pub fn updates_with_current(&mut self) -> Updates<'_, 'a, T, S> {
+        let current = (self.shared.header.written.load(Ordering::SeqCst) != 0).then(|| {
+            // closed value is still replayed and the iterator ends after.
+            let _ = self.try_has_changed();
+            self.read(|val| *val)
+        });
+        Updates {
+            watcher: self,
+            current,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{shared_memory_create, shared_memory_open, Watched, Watcher};
+
+    #[test]
+    fn updates_with_current() {
+        let path = "./test_file42";
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        let watched = Watched::<u8>::new_from_mem(&mut mem);
+
+        let mut mem2 = shared_memory_open(path, 1024).unwrap();
+        let mut watcher = Watcher::<u8>::new_from_mem(&mut mem2);
+        // nothing to replay before the first write.
+        assert!(watcher.updates_with_current().current.is_none());
+
+        watched.write(1);
+        watched.write(2);
+
+        let mut updates = watcher.updates_with_current();
+        assert_eq!(updates.next(), Some(2));
+        watched.write(3);
+        assert_eq!(updates.next(), Some(3));
+
+        let mut mem3 = shared_memory_open(path, 1024).unwrap();
+        let mut late = Watcher::<u8>::new_from_mem(&mut mem3);
+        watched.write(4);
+        drop(watched);
+        assert_eq!(late.updates_with_current().collect::<Vec<_>>(), [4]);
+        assert_eq!(late.updates().next(), None);
+    }
+}
@@ -0,0 +1,63 @@
+use std::{
+    mem,
+    sync::{Condvar, Mutex, OnceLock},
+    task::Waker,
+    thread,
+    time::Duration,
+};
+
+// Interval of waking pending polls. Writers in other processes can't notify them.
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+// Wakers of pending Watcher::poll_change in current process with poll-timer feature. They are
+// all woken by a timer thread after every interval and right away by a publish of Watched in
+// current process.
+struct Wakers {
+    list: Mutex<Vec<Waker>>,
+    cond: Condvar,
+}
+
+// initialized and timer thread spawned on the first registered waker.
+static WAKERS: OnceLock<Wakers> = OnceLock::new();
+
+fn wakers() -> &'static Wakers {
+    let mut spawn = false;
+    let wakers = WAKERS.get_or_init(|| {
+        spawn = true;
+        Wakers {
+            list: Mutex::new(Vec::new()),
+            cond: Condvar::new(),
+        }
+    });
+    if spawn {
+        thread::spawn(move || loop {
+            {
+                let mut list = wakers.list.lock().unwrap();
+                while list.is_empty() {
+                    list = wakers.cond.wait(list).unwrap();
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+            wake_all();
+        });
+    }
+    wakers
+}
+
+// register waker to be woken once. the same waker registered again is only woken once.
+pub(crate) fn register(waker: &Waker) {
+    let wakers = wakers();
+    let mut list = wakers.list.lock().unwrap();
+    if !list.iter().any(|w| w.will_wake(waker)) {
+        list.push(waker.clone());
+    }
+    wakers.cond.notify_one();
+}
+
+// wake all registered wakers. no op when nothing is ever registered.
+pub(crate) fn wake_all() {
+    if let Some(wakers) = WAKERS.get() {
+        let list = mem::take(&mut *wakers.list.lock().unwrap());
+        list.into_iter().for_each(Waker::wake);
+    }
+}
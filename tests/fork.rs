@@ -0,0 +1,33 @@
+//! Share value between parent and child process through anonymous memory.
+
+#![cfg(unix)]
+
+use ipc_watcher::{AnonMem, Watched, Watcher};
+
+#[test]
+fn fork() {
+    // map before fork so child inherit the same mapping.
+    let mut mem = AnonMem::new(1024).unwrap();
+
+    // SAFETY:
+    // child only use the inherited mapping and exit without returning to test harness.
+    let pid = unsafe { libc::fork() };
+    assert!(pid >= 0);
+
+    if pid == 0 {
+        let mut watcher = Watcher::<u64>::new_from_anon(&mut mem);
+        let code = match watcher.wait_for_change() {
+            Ok(_) if watcher.read(|val| *val) == 42 => 0,
+            _ => 1,
+        };
+        unsafe { libc::_exit(code) };
+    }
+
+    let watched = Watched::<u64>::new_from_anon(&mut mem);
+    watched.write(42);
+
+    let mut status = 0;
+    unsafe { libc::waitpid(pid, &mut status, 0) };
+    assert!(libc::WIFEXITED(status));
+    assert_eq!(libc::WEXITSTATUS(status), 0);
+}
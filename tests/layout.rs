@@ -0,0 +1,67 @@
+//! Layout of shared memory must be consistent for all kinds of `T`.
+
+use ipc_watcher::{shared_memory_create, validate_layout, Error, Watched, Watcher};
+
+#[repr(C, align(64))]
+#[derive(Clone, Copy)]
+struct CacheLine(u64);
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Mixed {
+    a: u8,
+    b: u64,
+    c: [u16; 3],
+}
+
+fn check<T: Copy + Send + Sync>(name: &str, val: T) {
+    let size = 64 * 1024;
+    validate_layout::<T>(size).unwrap();
+    assert!(matches!(
+        validate_layout::<T>(std::mem::size_of::<T>()),
+        Err(Error::MemoryTooSmall { .. })
+    ));
+
+    let path = format!("./test_layout_{}", name);
+    let mut mem = shared_memory_create(&path, size).unwrap();
+    let watched = Watched::<T>::new_from_mem(&mut mem);
+    watched.self_check().unwrap();
+    watched.write(val);
+    watched.self_check().unwrap();
+    drop(watched);
+
+    let mut mem = shared_memory_create(format!("{}_aligned", path), size).unwrap();
+    let watched = Watched::<T>::builder()
+        .data_align(4096)
+        .build(&mut mem)
+        .unwrap();
+    watched.self_check().unwrap();
+}
+
+#[test]
+fn layouts() {
+    check("u8", 1u8);
+    check("u64", 1u64);
+    check("tuple", (1u8, 2u64));
+    check("array", [7u8; 4096]);
+    check("cache_line", CacheLine(1));
+    check(
+        "mixed",
+        Mixed {
+            a: 1,
+            b: 2,
+            c: [3; 3],
+        },
+    );
+}
+
+#[test]
+fn watcher_first() {
+    let mut mem = shared_memory_create("./test_layout_watcher", 1024).unwrap();
+    {
+        let watcher = Watcher::<CacheLine>::new_from_mem(&mut mem);
+        drop(watcher);
+    }
+    let watched = Watched::<CacheLine>::new_from_mem(&mut mem);
+    watched.self_check().unwrap();
+}
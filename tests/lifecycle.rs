@@ -0,0 +1,52 @@
+//! Repeated create and drop of shared values must not leak any resource.
+
+use std::path::Path;
+
+use ipc_watcher::{shared_memory_create, shared_memory_open, Watched, Watcher};
+
+const CYCLES: usize = 300;
+
+#[cfg(target_os = "linux")]
+fn fd_count() -> usize {
+    std::fs::read_dir("/proc/self/fd").unwrap().count()
+}
+
+#[cfg(target_os = "linux")]
+fn shm_exists(os_id: &str) -> bool {
+    Path::new("/dev/shm")
+        .join(os_id.trim_start_matches('/'))
+        .exists()
+}
+
+#[test]
+fn create_drop_cycles() {
+    let path = "./test_lifecycle";
+
+    #[cfg(target_os = "linux")]
+    let fds = fd_count();
+
+    for i in 0..CYCLES {
+        let mut mem = shared_memory_create(path, 1024).unwrap();
+        #[cfg(target_os = "linux")]
+        let os_id = mem.get_os_id().to_string();
+
+        {
+            let watched = Watched::<usize>::new_from_mem(&mut mem);
+            watched.write(i);
+
+            let mut mem = shared_memory_open(path, 1024).unwrap();
+            let mut watcher = Watcher::<usize>::new_from_mem(&mut mem);
+            assert!(watcher.has_changed());
+            watcher.read(|val| assert_eq!(*val, i));
+        }
+
+        drop(mem);
+
+        assert!(!Path::new(path).exists(), "file link leaked");
+        #[cfg(target_os = "linux")]
+        assert!(!shm_exists(&os_id), "shared memory object leaked");
+    }
+
+    #[cfg(target_os = "linux")]
+    assert_eq!(fds, fd_count(), "file descriptor leaked");
+}
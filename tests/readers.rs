@@ -0,0 +1,124 @@
+//! Many readers in threads and processes hold the read lock while one writer keeps writing.
+
+use std::{
+    sync::{Arc, Barrier},
+    thread,
+    time::{Duration, Instant},
+};
+
+use ipc_watcher::{shared_memory_create, shared_memory_open, Watched, Watcher};
+
+const PATH: &str = "./test_readers";
+const THREADS: usize = 8;
+#[cfg(unix)]
+const PROCESSES: usize = 4;
+const WRITES: u64 = 2000;
+// interval between two writes.
+const WRITE_INTERVAL: Duration = Duration::from_millis(1);
+// pause between two reads. much smaller than WRITE_INTERVAL so every write is read by many
+// overlapping readers, and still leaves gaps for a writer on reader preferring rwlock.
+const READ_PAUSE: Duration = Duration::from_micros(10);
+// watchdog of the whole test. a starved writer or reader fails the test after it.
+const DEADLINE: Duration = Duration::from_secs(30);
+
+// every element is written with the same value so a torn read has different ones.
+type Value = [u64; 64];
+
+fn is_torn(val: &Value) -> bool {
+    val.iter().any(|v| *v != val[0])
+}
+
+// read until the last write is observed or the deadline.
+fn read_until_last(watcher: &Watcher<Value>, deadline: Instant) -> Result<(), &'static str> {
+    loop {
+        let remaining = deadline
+            .checked_duration_since(Instant::now())
+            .ok_or("reader made no progress before deadline")?;
+        let (torn, last) = watcher
+            .read_timeout(remaining, |val| (is_torn(val), val[0] == WRITES))
+            .map_err(|_| "reader starved before deadline")?;
+        if torn {
+            return Err("torn read");
+        }
+        if last {
+            return Ok(());
+        }
+        thread::sleep(READ_PAUSE);
+    }
+}
+
+#[test]
+fn concurrent_readers() {
+    let deadline = Instant::now() + DEADLINE;
+    let mut mem = shared_memory_create(PATH, 4096).unwrap();
+    let watched = Watched::<Value>::new_from_mem(&mut mem);
+    watched.write([0; 64]);
+
+    // fork before any thread is spawned. children only use shared memory they open and exit
+    // without returning to test harness.
+    #[cfg(unix)]
+    let pids = (0..PROCESSES)
+        .map(|_| {
+            // SAFETY:
+            // see above.
+            let pid = unsafe { libc::fork() };
+            assert!(pid >= 0);
+            if pid == 0 {
+                let code = match shared_memory_open(PATH, 4096) {
+                    Ok(mut mem) => {
+                        let watcher = Watcher::<Value>::new_from_mem(&mut mem);
+                        match read_until_last(&watcher, deadline) {
+                            Ok(_) => 0,
+                            Err(_) => 1,
+                        }
+                    }
+                    Err(_) => 2,
+                };
+                unsafe { libc::_exit(code) };
+            }
+            pid
+        })
+        .collect::<Vec<_>>();
+
+    let holding = Arc::new(Barrier::new(THREADS));
+    let started = Arc::new(Barrier::new(THREADS + 1));
+    let readers = (0..THREADS)
+        .map(|_| {
+            let holding = holding.clone();
+            let started = started.clone();
+            thread::spawn(move || {
+                let mut mem = shared_memory_open(PATH, 4096).unwrap();
+                let watcher = Watcher::<Value>::new_from_mem(&mut mem);
+                // every thread hold the read lock at the same time. deadlock otherwise.
+                watcher.read(|_| {
+                    holding.wait();
+                });
+                started.wait();
+                read_until_last(&watcher, deadline)
+            })
+        })
+        .collect::<Vec<_>>();
+    started.wait();
+
+    for i in 1..=WRITES {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if watched.write_timeout([i; 64], remaining).is_err() {
+            panic!("writer starved by readers at write {}", i);
+        }
+        thread::sleep(WRITE_INTERVAL);
+    }
+
+    for reader in readers {
+        if let Err(e) = reader.join().unwrap() {
+            panic!("reader thread failed: {}", e);
+        }
+    }
+
+    #[cfg(unix)]
+    for pid in pids {
+        let mut status = 0;
+        unsafe { libc::waitpid(pid, &mut status, 0) };
+        assert!(libc::WIFEXITED(status));
+        assert_eq!(libc::WEXITSTATUS(status), 0, "reader process failed");
+    }
+}